@@ -0,0 +1,200 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+
+use kingdom_click::game::kingdom::{KingdomID, WorldSettings};
+use kingdom_click::game::resource::ResourceTypes;
+use kingdom_click::game::simulation::Simulation;
+
+const USAGE: &str = "Usage: kingdom-sim [--turns N] [--runs N] [--seed N] [--kingdoms N] \
+[--policy idle|random|<script.ron>] [--format csv|json] [--out FILE]";
+
+// One entry per turn, cycled when the script is shorter than the run. `None` skips the turn.
+type Script = Vec<Option<(usize, ResourceTypes)>>;
+
+enum Policy {
+    Idle,
+    Random,
+    Scripted(Script),
+}
+
+enum Format {
+    Csv,
+    Json,
+}
+
+struct Options {
+    turns: usize,
+    runs: u64,
+    seed: u64,
+    kingdoms: usize,
+    policy: Policy,
+    format: Format,
+    out: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TurnRecord {
+    seed: u64,
+    turn: usize,
+    kingdom: usize,
+    name: String,
+    resources: BTreeMap<String, isize>,
+}
+
+fn parse_options() -> Result<Options, String> {
+    let mut options = Options {
+        turns: 100,
+        runs: 1,
+        seed: 0,
+        kingdoms: WorldSettings::default().kingdom_count,
+        policy: Policy::Random,
+        format: Format::Csv,
+        out: None,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("Missing value for {}", arg));
+        match arg.as_str() {
+            "--turns" => options.turns = value()?.parse().map_err(|e| format!("{}", e))?,
+            "--runs" => options.runs = value()?.parse().map_err(|e| format!("{}", e))?,
+            "--seed" => options.seed = value()?.parse().map_err(|e| format!("{}", e))?,
+            "--kingdoms" => options.kingdoms = value()?.parse().map_err(|e| format!("{}", e))?,
+            "--policy" => {
+                options.policy = match value()?.as_str() {
+                    "idle" => Policy::Idle,
+                    "random" => Policy::Random,
+                    path => {
+                        let data = fs::read_to_string(path).map_err(|e| format!("{}", e))?;
+                        Policy::Scripted(ron::from_str(&data).map_err(|e| format!("{}", e))?)
+                    }
+                }
+            }
+            "--format" => {
+                options.format = match value()?.as_str() {
+                    "csv" => Format::Csv,
+                    "json" => Format::Json,
+                    other => return Err(format!("Unknown format {}", other)),
+                }
+            }
+            "--out" => options.out = Some(value()?),
+            "--help" | "-h" => return Err(USAGE.to_string()),
+            other => return Err(format!("Unknown argument {}\n{}", other, USAGE)),
+        }
+    }
+    return Ok(options);
+}
+
+fn record_turn(sim: &mut Simulation, seed: u64, turn: usize, records: &mut Vec<TurnRecord>) {
+    for (kingdom, name) in sim.kingdoms() {
+        let resources = sim.resources(kingdom);
+        records.push(TurnRecord {
+            seed,
+            turn,
+            kingdom: kingdom.0,
+            name,
+            resources: ResourceTypes::iter()
+                .map(|resource_type| {
+                    (
+                        resource_type.as_ref().to_string(),
+                        resources.get(resource_type),
+                    )
+                })
+                .collect(),
+        });
+    }
+}
+
+fn run(options: &Options, seed: u64, records: &mut Vec<TurnRecord>) {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut sim = Simulation::with_settings(
+        seed,
+        WorldSettings {
+            kingdom_count: options.kingdoms,
+        },
+    );
+    record_turn(&mut sim, seed, 0, records);
+    for turn in 1..=options.turns {
+        match &options.policy {
+            Policy::Idle => sim.pass(),
+            Policy::Random => {
+                let kingdoms = sim.kingdoms();
+                let (kingdom, _) = kingdoms[rng.gen_range(0..kingdoms.len())];
+                let resource_types: Vec<ResourceTypes> = ResourceTypes::iter().collect();
+                let resource_type = resource_types[rng.gen_range(0..resource_types.len())];
+                sim.bless(kingdom, resource_type);
+            }
+            Policy::Scripted(script) => match script.get((turn - 1) % script.len().max(1)) {
+                Some(Some((kingdom, resource_type))) => {
+                    sim.bless(KingdomID(*kingdom), *resource_type)
+                }
+                _ => sim.pass(),
+            },
+        }
+        record_turn(&mut sim, seed, turn, records);
+        // Nothing happens once the game has been won or lost.
+        if let Some(outcome) = sim.outcome() {
+            eprintln!(
+                "Seed {}: {} on turn {} ({})",
+                seed,
+                if outcome.victory { "victory" } else { "defeat" },
+                outcome.turn,
+                outcome.name
+            );
+            break;
+        }
+    }
+}
+
+fn write_csv(records: &[TurnRecord], out: &mut dyn Write) -> io::Result<()> {
+    let headers: Vec<String> = ResourceTypes::iter()
+        .map(|resource_type| resource_type.as_ref().to_string())
+        .collect();
+    writeln!(out, "seed,turn,kingdom,name,{}", headers.join(","))?;
+    for record in records.iter() {
+        let values: Vec<String> = headers
+            .iter()
+            .map(|header| record.resources.get(header).unwrap_or(&0).to_string())
+            .collect();
+        writeln!(
+            out,
+            "{},{},{},\"{}\",{}",
+            record.seed,
+            record.turn,
+            record.kingdom,
+            record.name.replace('"', "\"\""),
+            values.join(",")
+        )?;
+    }
+    return Ok(());
+}
+
+fn main() {
+    let options = match parse_options() {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(2);
+        }
+    };
+
+    let mut records: Vec<TurnRecord> = Vec::new();
+    for seed in options.seed..options.seed + options.runs {
+        run(&options, seed, &mut records);
+    }
+
+    let mut out: Box<dyn Write> = match &options.out {
+        Some(path) => Box::new(fs::File::create(path).expect("Could not create output file")),
+        None => Box::new(io::stdout()),
+    };
+    let result = match options.format {
+        Format::Csv => write_csv(&records, &mut out),
+        Format::Json => serde_json::to_writer_pretty(&mut out, &records).map_err(io::Error::from),
+    };
+    result.expect("Could not write simulation results");
+}
@@ -0,0 +1,162 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::diplomacy::RelationShiftEvent;
+use super::effect::{Condition, Effect};
+use super::kingdom::{Kingdom, KingdomID};
+use super::resource::{KingdomResources, Resource, ResourceType, ResourceTypes};
+use super::rng::{GameRng, RngStream};
+use super::{kingdom_snapshot, ActionSource, ResourceAlterationEvent};
+
+const AI_DATA: &str = include_str!("../../assets/data/ai.ron");
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Need {
+    pub resource: ResourceTypes,
+    pub target: isize,
+    pub weight: f32,
+}
+
+impl Need {
+    // 0 when the resource is at or above its target, rising to `weight` as it empties.
+    pub fn urgency(&self, resources: &KingdomResources) -> f32 {
+        if self.target <= 0 {
+            return 0.;
+        }
+        let shortfall = (self.target - resources.get(self.resource)).clamp(0, self.target);
+        return self.weight * shortfall as f32 / self.target as f32;
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AiAction {
+    pub name: String,
+    // `{kingdom}` is replaced with the kingdom's name.
+    pub message: String,
+    pub needs: Vec<Need>,
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+    pub effects: Vec<Effect>,
+    #[serde(default)]
+    pub opinion: isize,
+}
+
+impl AiAction {
+    pub fn utility(&self, resources: &KingdomResources) -> f32 {
+        self.needs.iter().map(|need| need.urgency(resources)).sum()
+    }
+
+    pub fn can_take(&self, resources: &KingdomResources) -> bool {
+        self.conditions
+            .iter()
+            .all(|condition| condition.holds(resources))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersonalityDef {
+    pub name: String,
+    pub preferences: Vec<(String, f32)>,
+    pub whim: f32,
+}
+
+impl PersonalityDef {
+    pub fn preference(&self, action: &str) -> f32 {
+        self.preferences
+            .iter()
+            .find(|(name, _)| name == action)
+            .map_or(1., |(_, preference)| *preference)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KingdomAi {
+    pub actions: Vec<AiAction>,
+    pub personalities: Vec<PersonalityDef>,
+}
+
+impl Default for KingdomAi {
+    fn default() -> Self {
+        ron::from_str(AI_DATA).expect("Could not parse ai.ron")
+    }
+}
+
+impl KingdomAi {
+    pub fn personality(&self, name: &str) -> Option<&PersonalityDef> {
+        self.personalities
+            .iter()
+            .find(|personality| personality.name == name)
+    }
+
+    // Picks the action with the highest utility, after the personality's preferences and whims.
+    // Kingdoms with nothing to do rest.
+    pub fn choose<R: Rng>(
+        &self,
+        personality: Option<&PersonalityDef>,
+        resources: &KingdomResources,
+        rng: &mut R,
+    ) -> Option<&AiAction> {
+        let mut best: Option<(&AiAction, f32)> = None;
+        for action in self
+            .actions
+            .iter()
+            .filter(|action| action.can_take(resources))
+        {
+            let mut score = action.utility(resources);
+            if let Some(personality) = personality {
+                score *= personality.preference(&action.name);
+                if personality.whim > 0. {
+                    score += rng.gen_range(0. ..personality.whim);
+                }
+            }
+            if score > 0. && best.map_or(true, |(_, best_score)| score > best_score) {
+                best = Some((action, score));
+            }
+        }
+        return best.map(|(action, _)| action);
+    }
+}
+
+// The name of one of the personalities in ai.ron. Unknown names act without preferences.
+#[derive(Debug, Component, Clone, Default)]
+pub struct Personality(pub String);
+
+// Runs during `TurnState::CountingChanges`, after random events, so kingdoms respond to what has
+// just befallen them.
+pub fn kingdom_ai_turn(
+    ai: Res<KingdomAi>,
+    mut rng: ResMut<GameRng>,
+    kingdom_query: Query<(&KingdomID, &Name, &Personality), With<Kingdom>>,
+    resource_query: Query<(&Resource, &ResourceType, &KingdomID)>,
+    mut ev_resource_changes: EventWriter<ResourceAlterationEvent>,
+    mut ev_shift: EventWriter<RelationShiftEvent>,
+) {
+    // Kingdoms decide in ID order so the same seed always makes the same choices.
+    let mut kingdoms: Vec<(&KingdomID, &Name, &Personality)> = kingdom_query.iter().collect();
+    kingdoms.sort_by_key(|(KingdomID(id), _, _)| *id);
+
+    let rng = rng.stream(RngStream::AI);
+    for (kingdom, name, Personality(personality)) in kingdoms {
+        let resources = kingdom_snapshot(*kingdom, resource_query.iter());
+        if let Some(action) = ai.choose(ai.personality(personality), &resources, rng) {
+            ev_resource_changes.send(ResourceAlterationEvent {
+                source: ActionSource::World,
+                message: action.message.replace("{kingdom}", &name.to_string()),
+                changes: action
+                    .effects
+                    .iter()
+                    .map(|effect| (*kingdom, effect.clone()))
+                    .collect(),
+                cost: 0,
+            });
+            if action.opinion != 0 {
+                ev_shift.send(RelationShiftEvent {
+                    kingdom: *kingdom,
+                    other: None,
+                    amount: action.opinion,
+                });
+            }
+        }
+    }
+}
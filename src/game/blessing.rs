@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+
+use super::effect::{describe_all, Effect};
+use super::kingdom::KingdomID;
+use super::resource::ResourceTypes;
+use super::{ActionSource, ResourceAlterationEvent};
+
+const BLESSINGS_DATA: &str = include_str!("../../assets/data/blessings.ron");
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Blessing {
+    // Only needed for locked blessings, which are unlocked by name.
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub locked: bool,
+    // Faith the god pays to cast it, before any discount. Costly blessings are miracles.
+    #[serde(default)]
+    pub cost: isize,
+    pub resource: ResourceTypes,
+    pub message: String,
+    pub effects: Vec<Effect>,
+}
+
+impl Blessing {
+    pub fn title(&self) -> String {
+        if self.name.is_empty() {
+            self.resource.as_ref().to_string()
+        } else {
+            self.name.clone()
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        if self.cost > 0 {
+            return format!(
+                "{} ({} faith): {}",
+                self.title(),
+                self.cost,
+                describe_all(&self.effects)
+            );
+        }
+        format!("{}: {}", self.title(), describe_all(&self.effects))
+    }
+
+    pub fn alteration(&self, kingdom: KingdomID) -> ResourceAlterationEvent {
+        ResourceAlterationEvent {
+            source: ActionSource::Player,
+            message: self.message.clone(),
+            changes: self
+                .effects
+                .iter()
+                .map(|effect| (kingdom, effect.clone()))
+                .collect(),
+            cost: self.cost,
+        }
+    }
+}
+
+pub struct Blessings(pub Vec<Blessing>);
+
+impl Default for Blessings {
+    fn default() -> Self {
+        Blessings(ron::from_str(BLESSINGS_DATA).expect("Could not parse blessings.ron"))
+    }
+}
+
+impl Blessings {
+    pub fn for_resource(&self, resource: ResourceTypes) -> Option<&Blessing> {
+        self.0
+            .iter()
+            .find(|blessing| blessing.resource == resource && !blessing.locked)
+    }
+
+    pub fn by_name(&self, name: &str) -> Option<&Blessing> {
+        self.0.iter().find(|blessing| blessing.name == name)
+    }
+}
@@ -0,0 +1,268 @@
+use bevy::ecs::event::{Events, ManualEventReader};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::{Deserialize, Serialize};
+
+use super::effect::Effect;
+use super::kingdom::{Kingdom, KingdomID};
+use super::resource::ResourceTypes;
+use super::{ActionSource, Log, ResourceAlterationEvent};
+
+pub const OPINION_LIMIT: isize = 100;
+
+// Opinion thresholds at which relations change. Each agreement is made at the first and broken
+// below the second, so relations don't flicker from turn to turn.
+pub const TRADE_THRESHOLDS: (isize, isize) = (30, 0);
+pub const TREATY_THRESHOLDS: (isize, isize) = (60, 20);
+// Rivalries start at or below the first and end above the second; wars likewise.
+pub const RIVALRY_THRESHOLDS: (isize, isize) = (-30, -10);
+pub const WAR_THRESHOLDS: (isize, isize) = (-70, -40);
+
+// How two kingdoms feel about each other. `a` is always the lower ID.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Relation {
+    pub a: KingdomID,
+    pub b: KingdomID,
+    pub opinion: isize,
+    pub treaty: bool,
+    pub trade: bool,
+    pub rivalry: bool,
+    pub war: bool,
+}
+
+impl Relation {
+    pub fn new(a: KingdomID, b: KingdomID) -> Self {
+        let (a, b) = if a.0 <= b.0 { (a, b) } else { (b, a) };
+        Relation {
+            a,
+            b,
+            opinion: 0,
+            treaty: false,
+            trade: false,
+            rivalry: false,
+            war: false,
+        }
+    }
+
+    pub fn involves(&self, kingdom: KingdomID) -> bool {
+        self.a == kingdom || self.b == kingdom
+    }
+
+    pub fn other(&self, kingdom: KingdomID) -> KingdomID {
+        if self.a == kingdom {
+            self.b
+        } else {
+            self.a
+        }
+    }
+
+    pub fn shift(&mut self, amount: isize) {
+        self.opinion = (self.opinion + amount).clamp(-OPINION_LIMIT, OPINION_LIMIT);
+    }
+
+    pub fn status(&self) -> String {
+        let mut status: Vec<&str> = Vec::new();
+        if self.war {
+            status.push("at war");
+        } else if self.rivalry {
+            status.push("rivals");
+        }
+        if self.treaty {
+            status.push("treaty");
+        }
+        if self.trade {
+            status.push("trading");
+        }
+        if status.is_empty() {
+            status.push("neutral");
+        }
+        return status.join(", ");
+    }
+
+    // Makes and breaks agreements to match the opinion, returning what changed.
+    fn settle(&mut self) -> Vec<&'static str> {
+        let mut changes = Vec::new();
+        if !self.trade && !self.war && self.opinion >= TRADE_THRESHOLDS.0 {
+            self.trade = true;
+            changes.push("sign a trade agreement");
+        } else if self.trade && (self.war || self.opinion < TRADE_THRESHOLDS.1) {
+            self.trade = false;
+            changes.push("end their trade agreement");
+        }
+        if !self.treaty && !self.war && self.opinion >= TREATY_THRESHOLDS.0 {
+            self.treaty = true;
+            changes.push("swear a treaty of friendship");
+        } else if self.treaty && self.opinion < TREATY_THRESHOLDS.1 {
+            self.treaty = false;
+            changes.push("tear up their treaty");
+        }
+        if !self.rivalry && self.opinion <= RIVALRY_THRESHOLDS.0 {
+            self.rivalry = true;
+            changes.push("become rivals");
+        } else if self.rivalry && self.opinion > RIVALRY_THRESHOLDS.1 {
+            self.rivalry = false;
+            changes.push("put their rivalry aside");
+        }
+        if !self.war && !self.treaty && self.rivalry && self.opinion <= WAR_THRESHOLDS.0 {
+            self.war = true;
+            changes.push("go to war");
+        } else if self.war && self.opinion > WAR_THRESHOLDS.1 {
+            self.war = false;
+            changes.push("make peace");
+        }
+        return changes;
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Relations(pub Vec<Relation>);
+
+impl Relations {
+    pub fn get(&self, a: KingdomID, b: KingdomID) -> Option<&Relation> {
+        self.0
+            .iter()
+            .find(|relation| relation.involves(a) && relation.involves(b) && a != b)
+    }
+
+    pub fn get_mut(&mut self, a: KingdomID, b: KingdomID) -> Option<&mut Relation> {
+        self.0
+            .iter_mut()
+            .find(|relation| relation.involves(a) && relation.involves(b) && a != b)
+    }
+
+    // Every relation `kingdom` is part of.
+    pub fn of(&self, kingdom: KingdomID) -> impl Iterator<Item = &Relation> {
+        self.0
+            .iter()
+            .filter(move |relation| relation.involves(kingdom))
+    }
+
+    // Adds a neutral relation for every new pair of kingdoms and forgets kingdoms that are gone.
+    pub fn sync(&mut self, kingdoms: &[KingdomID]) {
+        self.0
+            .retain(|relation| kingdoms.contains(&relation.a) && kingdoms.contains(&relation.b));
+        for (i, a) in kingdoms.iter().enumerate() {
+            for b in kingdoms.iter().skip(i + 1) {
+                if self.get(*a, *b).is_none() {
+                    self.0.push(Relation::new(*a, *b));
+                }
+            }
+        }
+        self.0.sort_by_key(|relation| (relation.a.0, relation.b.0));
+    }
+}
+
+// Shifts how `kingdom` and `other` feel about each other, or how `kingdom` and everyone else
+// do when `other` is `None`.
+#[derive(Debug, Clone)]
+pub struct RelationShiftEvent {
+    pub kingdom: KingdomID,
+    pub other: Option<KingdomID>,
+    pub amount: isize,
+}
+
+// Runs in `CoreStage::PostUpdate`, once kingdoms spawned or despawned during the turn exist or are
+// gone, so relations set up alongside them aren't forgotten before their kingdoms arrive.
+pub fn sync_relations(
+    kingdom_query: Query<&KingdomID, With<Kingdom>>,
+    mut relations: ResMut<Relations>,
+) {
+    let kingdoms: Vec<KingdomID> = kingdom_query.iter().cloned().collect();
+    let known = relations.0.len();
+    let expected = kingdoms.len() * kingdoms.len().saturating_sub(1) / 2;
+    if known != expected
+        || relations
+            .0
+            .iter()
+            .any(|relation| !kingdoms.contains(&relation.a) || !kingdoms.contains(&relation.b))
+    {
+        relations.sync(&kingdoms);
+    }
+}
+
+// Runs during `TurnState::CountingChanges`, after the kingdoms have acted. The god blessing one
+// kingdom makes its neighbours envious; blessing both at once brings them together.
+pub fn update_relations(
+    kingdom_query: Query<(&KingdomID, &Name), With<Kingdom>>,
+    mut relations: ResMut<Relations>,
+    // Read and written here, so it can't be an `EventReader` and `EventWriter` pair.
+    mut ev_resource_changes: ResMut<Events<ResourceAlterationEvent>>,
+    mut resource_change_reader: Local<ManualEventReader<ResourceAlterationEvent>>,
+    mut ev_shift: EventReader<RelationShiftEvent>,
+    mut log: ResMut<Log>,
+) {
+    let names: HashMap<KingdomID, String> = kingdom_query
+        .iter()
+        .map(|(id, name)| (*id, name.to_string()))
+        .collect();
+
+    let blessed: Vec<Vec<KingdomID>> = resource_change_reader
+        .iter(&ev_resource_changes)
+        .filter(|event| matches!(event.source, ActionSource::Player | ActionSource::Miracle))
+        .map(|event| event.changes.iter().map(|(kingdom, _)| *kingdom).collect())
+        .collect();
+    for targets in blessed {
+        for relation in relations.0.iter_mut() {
+            match (targets.contains(&relation.a), targets.contains(&relation.b)) {
+                (true, true) => relation.shift(1),
+                (true, false) | (false, true) => relation.shift(-1),
+                _ => {}
+            }
+        }
+    }
+    for shift in ev_shift.iter() {
+        for relation in relations.0.iter_mut() {
+            let affected = relation.involves(shift.kingdom)
+                && shift
+                    .other
+                    .map_or(true, |other| relation.other(shift.kingdom) == other);
+            if affected {
+                relation.shift(shift.amount);
+            }
+        }
+    }
+
+    let mut alterations: Vec<ResourceAlterationEvent> = Vec::new();
+    for relation in relations.0.iter_mut() {
+        // Old grudges and friendships fade unless something keeps them alive.
+        if relation.trade {
+            relation.shift(1);
+        } else {
+            relation.shift(-relation.opinion.signum());
+        }
+
+        let name_a = names.get(&relation.a).cloned().unwrap_or_default();
+        let name_b = names.get(&relation.b).cloned().unwrap_or_default();
+        for change in relation.settle() {
+            log.0.push((
+                format!("{} and {} {}.", name_a, name_b, change),
+                String::new(),
+            ));
+        }
+        if relation.trade {
+            alterations.push(ResourceAlterationEvent {
+                source: ActionSource::World,
+                message: format!("Caravans travel between {} and {}.", name_a, name_b),
+                changes: vec![
+                    (relation.a, Effect::Add(ResourceTypes::Industry, 2)),
+                    (relation.b, Effect::Add(ResourceTypes::Industry, 2)),
+                ],
+                cost: 0,
+            });
+        }
+        if relation.war {
+            alterations.push(ResourceAlterationEvent {
+                source: ActionSource::World,
+                message: format!("War rages between {} and {}.", name_a, name_b),
+                changes: vec![
+                    (relation.a, Effect::Add(ResourceTypes::Happiness, -2)),
+                    (relation.b, Effect::Add(ResourceTypes::Happiness, -2)),
+                ],
+                cost: 0,
+            });
+        }
+    }
+    for alteration in alterations {
+        ev_resource_changes.send(alteration);
+    }
+}
@@ -0,0 +1,48 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::effect::Effect;
+use super::kingdom::{Kingdom, KingdomID};
+use super::{ActionSource, ResourceAlterationEvent};
+
+const ECONOMY_DATA: &str = include_str!("../../assets/data/economy.ron");
+
+// Production, consumption and upkeep that every kingdom goes through each turn.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EconomyRule {
+    pub name: String,
+    pub effects: Vec<Effect>,
+}
+
+pub struct Economy(pub Vec<EconomyRule>);
+
+impl Default for Economy {
+    fn default() -> Self {
+        Economy(ron::from_str(ECONOMY_DATA).expect("Could not parse economy.ron"))
+    }
+}
+
+// Runs during `TurnState::CountingChanges`, after everything else has had its say, so the rules
+// work on the resources the kingdom will actually have. Rules apply in order, each seeing the
+// results of the ones before.
+pub fn run_economy(
+    economy: Res<Economy>,
+    kingdom_query: Query<(&KingdomID, &Name), With<Kingdom>>,
+    mut ev_resource_changes: EventWriter<ResourceAlterationEvent>,
+) {
+    let mut kingdoms: Vec<(&KingdomID, &Name)> = kingdom_query.iter().collect();
+    kingdoms.sort_by_key(|(KingdomID(id), _)| *id);
+    for (kingdom, name) in kingdoms {
+        ev_resource_changes.send(ResourceAlterationEvent {
+            source: ActionSource::Economy,
+            message: format!("Life goes on in {}.", name),
+            changes: economy
+                .0
+                .iter()
+                .flat_map(|rule| rule.effects.iter())
+                .map(|effect| (*kingdom, effect.clone()))
+                .collect(),
+            cost: 0,
+        });
+    }
+}
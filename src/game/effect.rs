@@ -0,0 +1,423 @@
+use serde::{Deserialize, Serialize};
+
+use super::resource::{KingdomResources, ModifierLayer, ResourceModification, ResourceTypes};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Condition {
+    Above(ResourceTypes, isize),
+    Below(ResourceTypes, isize),
+    All(Vec<Condition>),
+    Any(Vec<Condition>),
+    Not(Box<Condition>),
+}
+
+impl Condition {
+    pub fn holds(&self, resources: &KingdomResources) -> bool {
+        match self {
+            Condition::Above(resource, threshold) => resources.get(*resource) > *threshold,
+            Condition::Below(resource, threshold) => resources.get(*resource) < *threshold,
+            Condition::All(conditions) => conditions.iter().all(|c| c.holds(resources)),
+            Condition::Any(conditions) => conditions.iter().any(|c| c.holds(resources)),
+            Condition::Not(condition) => !condition.holds(resources),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Condition::Above(resource, threshold) => {
+                format!("{} above {}", resource.as_ref(), threshold)
+            }
+            Condition::Below(resource, threshold) => {
+                format!("{} below {}", resource.as_ref(), threshold)
+            }
+            Condition::All(conditions) => conditions
+                .iter()
+                .map(|c| c.describe())
+                .collect::<Vec<String>>()
+                .join(" and "),
+            Condition::Any(conditions) => conditions
+                .iter()
+                .map(|c| c.describe())
+                .collect::<Vec<String>>()
+                .join(" or "),
+            Condition::Not(condition) => format!("not ({})", condition.describe()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Effect {
+    Add(ResourceTypes, isize),
+    Multiply(ResourceTypes, f32),
+    Set(ResourceTypes, isize),
+    Percent(ResourceTypes, isize),
+    // Adds `amount` to `resource` for every `per` points of `of`.
+    Scaled {
+        resource: ResourceTypes,
+        amount: isize,
+        per: isize,
+        of: ResourceTypes,
+    },
+    Transfer {
+        from: ResourceTypes,
+        to: ResourceTypes,
+        amount: isize,
+    },
+    Conditional {
+        condition: Condition,
+        then: Vec<Effect>,
+        otherwise: Vec<Effect>,
+    },
+    // Adds a modifier to the kingdom rather than changing anything this instant.
+    Modifier(ResourceModification),
+}
+
+impl Effect {
+    // Effects are applied in sequence to a snapshot, so later effects see the results of
+    // earlier ones. The caller diffs the snapshot to find the actual change.
+    pub fn apply(&self, resources: &mut KingdomResources) {
+        match self {
+            Effect::Add(resource, amount) => resources.add(*resource, *amount),
+            Effect::Multiply(resource, factor) => {
+                let value = resources.get(*resource);
+                resources.set(*resource, (value as f32 * factor).round() as isize);
+            }
+            Effect::Set(resource, value) => resources.set(*resource, *value),
+            Effect::Percent(resource, percent) => {
+                let value = resources.get(*resource);
+                resources.add(*resource, value * percent / 100);
+            }
+            Effect::Scaled {
+                resource,
+                amount,
+                per,
+                of,
+            } => {
+                let units = resources.get(*of).max(0) / (*per).max(1);
+                resources.add(*resource, amount * units);
+            }
+            Effect::Transfer { from, to, amount } => {
+                let moved = (*amount).min(resources.get(*from)).max(0);
+                resources.add(*from, -moved);
+                resources.add(*to, moved);
+            }
+            Effect::Conditional {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let branch = if condition.holds(resources) {
+                    then
+                } else {
+                    otherwise
+                };
+                for effect in branch.iter() {
+                    effect.apply(resources);
+                }
+            }
+            Effect::Modifier(_) => {}
+        }
+    }
+
+    // The modifiers this effect grants, given the kingdom's resources at the time.
+    pub fn modifiers(&self, resources: &KingdomResources) -> Vec<ResourceModification> {
+        match self {
+            Effect::Modifier(modifier) => vec![modifier.clone()],
+            Effect::Conditional {
+                condition,
+                then,
+                otherwise,
+            } => {
+                let branch = if condition.holds(resources) {
+                    then
+                } else {
+                    otherwise
+                };
+                branch
+                    .iter()
+                    .flat_map(|effect| effect.modifiers(resources))
+                    .collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    // The same effect aimed at `resource` instead. Only effects on a single resource change.
+    pub fn retargeted(&self, resource: ResourceTypes) -> Effect {
+        match self {
+            Effect::Add(_, amount) => Effect::Add(resource, *amount),
+            Effect::Multiply(_, factor) => Effect::Multiply(resource, *factor),
+            Effect::Set(_, value) => Effect::Set(resource, *value),
+            Effect::Percent(_, percent) => Effect::Percent(resource, *percent),
+            effect => effect.clone(),
+        }
+    }
+
+    // The effect with its amounts multiplied by `power`. Effects that set or scale a resource
+    // are left alone.
+    pub fn amplified(&self, power: isize) -> Effect {
+        match self {
+            Effect::Add(resource, amount) => Effect::Add(*resource, amount * power),
+            Effect::Percent(resource, percent) => Effect::Percent(*resource, percent * power),
+            Effect::Scaled {
+                resource,
+                amount,
+                per,
+                of,
+            } => Effect::Scaled {
+                resource: *resource,
+                amount: amount * power,
+                per: *per,
+                of: *of,
+            },
+            Effect::Transfer { from, to, amount } => Effect::Transfer {
+                from: *from,
+                to: *to,
+                amount: amount * power,
+            },
+            Effect::Conditional {
+                condition,
+                then,
+                otherwise,
+            } => Effect::Conditional {
+                condition: condition.clone(),
+                then: then.iter().map(|effect| effect.amplified(power)).collect(),
+                otherwise: otherwise
+                    .iter()
+                    .map(|effect| effect.amplified(power))
+                    .collect(),
+            },
+            Effect::Modifier(modifier) => match modifier.layer {
+                ModifierLayer::Add(amount) => Effect::Modifier(ResourceModification {
+                    layer: ModifierLayer::Add(amount * power),
+                    ..modifier.clone()
+                }),
+                _ => self.clone(),
+            },
+            effect => effect.clone(),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Effect::Add(resource, amount) => format!("{:+} {}", amount, resource.as_ref()),
+            Effect::Multiply(resource, factor) => format!("{} x{}", resource.as_ref(), factor),
+            Effect::Set(resource, value) => format!("Set {} to {}", resource.as_ref(), value),
+            Effect::Percent(resource, percent) => {
+                format!("{:+}% {}", percent, resource.as_ref())
+            }
+            Effect::Scaled {
+                resource,
+                amount,
+                per,
+                of,
+            } => format!(
+                "{:+} {} per {} {}",
+                amount,
+                resource.as_ref(),
+                per,
+                of.as_ref()
+            ),
+            Effect::Transfer { from, to, amount } => {
+                format!("Move {} {} to {}", amount, from.as_ref(), to.as_ref())
+            }
+            Effect::Conditional {
+                condition,
+                then,
+                otherwise,
+            } => {
+                if otherwise.is_empty() {
+                    format!("If {}: {}", condition.describe(), describe_all(then))
+                } else {
+                    format!(
+                        "If {}: {}, otherwise {}",
+                        condition.describe(),
+                        describe_all(then),
+                        describe_all(otherwise)
+                    )
+                }
+            }
+            Effect::Modifier(modifier) => modifier.describe(),
+        }
+    }
+}
+
+pub fn describe_all(effects: &[Effect]) -> String {
+    effects
+        .iter()
+        .map(|e| e.describe())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ResourceTypes::*;
+
+    fn resources(values: &[(ResourceTypes, isize)]) -> KingdomResources {
+        let mut resources = KingdomResources::new();
+        for (resource, value) in values.iter() {
+            resources.set(*resource, *value);
+        }
+        return resources;
+    }
+
+    fn applied(effect: Effect, values: &[(ResourceTypes, isize)]) -> KingdomResources {
+        let mut resources = resources(values);
+        effect.apply(&mut resources);
+        return resources;
+    }
+
+    #[test]
+    fn transfer_moves_no_more_than_the_source_holds() {
+        let transfer = Effect::Transfer {
+            from: Food,
+            to: Military,
+            amount: 20,
+        };
+        let after = applied(transfer.clone(), &[(Food, 50), (Military, 5)]);
+        assert_eq!((after.get(Food), after.get(Military)), (30, 25));
+        let after = applied(transfer.clone(), &[(Food, 8), (Military, 5)]);
+        assert_eq!((after.get(Food), after.get(Military)), (0, 13));
+        let after = applied(transfer, &[(Food, -4), (Military, 5)]);
+        assert_eq!((after.get(Food), after.get(Military)), (-4, 5));
+    }
+
+    #[test]
+    fn conditional_applies_the_branch_that_holds() {
+        let conditional = Effect::Conditional {
+            condition: Condition::Below(Food, 10),
+            then: vec![Effect::Add(Populace, -3)],
+            otherwise: vec![Effect::Add(Populace, 2), Effect::Add(Happiness, 1)],
+        };
+        let after = applied(conditional.clone(), &[(Food, 5), (Populace, 50)]);
+        assert_eq!((after.get(Populace), after.get(Happiness)), (47, 0));
+        let after = applied(conditional, &[(Food, 10), (Populace, 50)]);
+        assert_eq!((after.get(Populace), after.get(Happiness)), (52, 1));
+    }
+
+    #[test]
+    fn percent_rounds_toward_zero_on_negative_values() {
+        assert_eq!(
+            applied(Effect::Percent(Food, 50), &[(Food, -10)]).get(Food),
+            -15
+        );
+        assert_eq!(
+            applied(Effect::Percent(Food, -10), &[(Food, 55)]).get(Food),
+            50
+        );
+        assert_eq!(
+            applied(Effect::Percent(Food, -10), &[(Food, -55)]).get(Food),
+            -50
+        );
+    }
+
+    #[test]
+    fn effects_apply_in_sequence() {
+        let mut resources = resources(&[(Industry, 10)]);
+        for effect in [
+            Effect::Add(Industry, 40),
+            Effect::Scaled {
+                resource: Food,
+                amount: 1,
+                per: 5,
+                of: Industry,
+            },
+        ] {
+            effect.apply(&mut resources);
+        }
+        assert_eq!(resources.get(Food), 10);
+    }
+
+    #[test]
+    fn amplified_scales_amounts_but_not_sets() {
+        assert_eq!(Effect::Add(Food, 3).amplified(2), Effect::Add(Food, 6));
+        assert_eq!(
+            Effect::Percent(Food, -5).amplified(3),
+            Effect::Percent(Food, -15)
+        );
+        assert_eq!(Effect::Set(Food, 3).amplified(2), Effect::Set(Food, 3));
+        assert_eq!(
+            Effect::Multiply(Food, 1.5).amplified(2),
+            Effect::Multiply(Food, 1.5)
+        );
+        assert_eq!(
+            Effect::Conditional {
+                condition: Condition::Above(Food, 1),
+                then: vec![Effect::Add(Food, 1)],
+                otherwise: vec![Effect::Transfer {
+                    from: Food,
+                    to: Faith,
+                    amount: 2,
+                }],
+            }
+            .amplified(4),
+            Effect::Conditional {
+                condition: Condition::Above(Food, 1),
+                then: vec![Effect::Add(Food, 4)],
+                otherwise: vec![Effect::Transfer {
+                    from: Food,
+                    to: Faith,
+                    amount: 8,
+                }],
+            }
+        );
+    }
+
+    #[test]
+    fn retargeted_only_moves_single_resource_effects() {
+        assert_eq!(
+            Effect::Add(Food, 8).retargeted(Faith),
+            Effect::Add(Faith, 8)
+        );
+        assert_eq!(
+            Effect::Set(Food, 8).retargeted(Faith),
+            Effect::Set(Faith, 8)
+        );
+        assert_eq!(
+            Effect::Percent(Food, 8).retargeted(Faith),
+            Effect::Percent(Faith, 8)
+        );
+        let transfer = Effect::Transfer {
+            from: Food,
+            to: Military,
+            amount: 5,
+        };
+        assert_eq!(transfer.retargeted(Faith), transfer);
+    }
+
+    #[test]
+    fn describe_reads_each_kind_of_effect() {
+        assert_eq!(Effect::Add(Food, 3).describe(), "+3 Food");
+        assert_eq!(Effect::Percent(Faith, -5).describe(), "-5% Faith");
+        assert_eq!(Effect::Set(Happiness, 50).describe(), "Set Happiness to 50");
+        assert_eq!(
+            Effect::Transfer {
+                from: Food,
+                to: Military,
+                amount: 5,
+            }
+            .describe(),
+            "Move 5 Food to Military"
+        );
+        assert_eq!(
+            Effect::Conditional {
+                condition: Condition::Below(Food, 10),
+                then: vec![Effect::Add(Populace, -3)],
+                otherwise: vec![],
+            }
+            .describe(),
+            "If Food below 10: -3 Populace"
+        );
+        assert_eq!(
+            Effect::Conditional {
+                condition: Condition::Above(Food, 75),
+                then: vec![Effect::Add(Populace, 2)],
+                otherwise: vec![Effect::Add(Happiness, -1), Effect::Add(Faith, 1)],
+            }
+            .describe(),
+            "If Food above 75: +2 Populace, otherwise -1 Happiness, +1 Faith"
+        );
+    }
+}
@@ -0,0 +1,120 @@
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::diplomacy::RelationShiftEvent;
+use super::effect::{Condition, Effect};
+use super::kingdom::{Kingdom, KingdomID};
+use super::resource::{KingdomResources, Resource, ResourceType};
+use super::rng::{GameRng, RngStream};
+use super::{kingdom_snapshot, ActionSource, ResourceAlterationEvent};
+
+const EVENTS_DATA: &str = include_str!("../../assets/data/events.ron");
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KingdomEventDef {
+    pub name: String,
+    // `{kingdom}` is replaced with the kingdom's name.
+    pub message: String,
+    pub weight: u32,
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+    pub effects: Vec<Effect>,
+    #[serde(default)]
+    pub opinion: isize,
+}
+
+impl KingdomEventDef {
+    pub fn can_trigger(&self, resources: &KingdomResources) -> bool {
+        self.conditions
+            .iter()
+            .all(|condition| condition.holds(resources))
+    }
+
+    pub fn alteration(&self, kingdom: KingdomID, kingdom_name: &str) -> ResourceAlterationEvent {
+        ResourceAlterationEvent {
+            source: ActionSource::World,
+            message: self.message.replace("{kingdom}", kingdom_name),
+            changes: self
+                .effects
+                .iter()
+                .map(|effect| (kingdom, effect.clone()))
+                .collect(),
+            cost: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KingdomEvents {
+    pub chance: f32,
+    pub events: Vec<KingdomEventDef>,
+}
+
+impl Default for KingdomEvents {
+    fn default() -> Self {
+        ron::from_str(EVENTS_DATA).expect("Could not parse events.ron")
+    }
+}
+
+impl KingdomEvents {
+    pub fn by_name(&self, name: &str) -> Option<&KingdomEventDef> {
+        self.events.iter().find(|event| event.name == name)
+    }
+
+    // Picks one of the events whose conditions hold, weighted by `weight`.
+    pub fn choose<R: Rng>(
+        &self,
+        resources: &KingdomResources,
+        rng: &mut R,
+    ) -> Option<&KingdomEventDef> {
+        let eligible: Vec<&KingdomEventDef> = self
+            .events
+            .iter()
+            .filter(|event| event.weight > 0 && event.can_trigger(resources))
+            .collect();
+        let total: u32 = eligible.iter().map(|event| event.weight).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut roll = rng.gen_range(0..total);
+        for event in eligible {
+            if roll < event.weight {
+                return Some(event);
+            }
+            roll -= event.weight;
+        }
+        return None;
+    }
+}
+
+pub fn roll_kingdom_events(
+    events: Res<KingdomEvents>,
+    mut rng: ResMut<GameRng>,
+    kingdom_query: Query<(&KingdomID, &Name), With<Kingdom>>,
+    resource_query: Query<(&Resource, &ResourceType, &KingdomID)>,
+    mut ev_resource_changes: EventWriter<ResourceAlterationEvent>,
+    mut ev_shift: EventWriter<RelationShiftEvent>,
+) {
+    // Kingdoms are visited in ID order so the same seed always rolls the same events.
+    let mut kingdoms: Vec<(&KingdomID, &Name)> = kingdom_query.iter().collect();
+    kingdoms.sort_by_key(|(KingdomID(id), _)| *id);
+
+    let rng = rng.stream(RngStream::Events);
+    for (kingdom, name) in kingdoms {
+        if !rng.gen_bool(events.chance.clamp(0., 1.) as f64) {
+            continue;
+        }
+        let resources = kingdom_snapshot(*kingdom, resource_query.iter());
+        if let Some(event) = events.choose(&resources, rng) {
+            ev_resource_changes.send(event.alteration(*kingdom, &name.to_string()));
+            if event.opinion != 0 {
+                ev_shift.send(RelationShiftEvent {
+                    kingdom: *kingdom,
+                    other: None,
+                    amount: event.opinion,
+                });
+            }
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::ai::PersonalityDef;
+use super::resource::{ResourceTypes, STARTING_RESOURCES};
+
+const GENERATION_DATA: &str = include_str!("../../assets/data/generation.ron");
+
+// How many names to draw before settling for a numbered one.
+const NAME_ATTEMPTS: usize = 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CultureDef {
+    pub name: String,
+    // Added to the starting value of each listed resource.
+    #[serde(default)]
+    pub resources: Vec<(ResourceTypes, isize)>,
+    // Names of traits from traits.ron every kingdom of this culture has.
+    #[serde(default)]
+    pub traits: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KingdomGenerator {
+    pub prefixes: Vec<String>,
+    pub suffixes: Vec<String>,
+    pub ruler_titles: Vec<String>,
+    pub ruler_names: Vec<String>,
+    pub base: isize,
+    pub spread: isize,
+    pub cultures: Vec<CultureDef>,
+}
+
+impl Default for KingdomGenerator {
+    fn default() -> Self {
+        ron::from_str(GENERATION_DATA).expect("Could not parse generation.ron")
+    }
+}
+
+// Everything a new kingdom starts with, before it is spawned.
+#[derive(Debug, Clone)]
+pub struct GeneratedKingdom {
+    pub name: String,
+    pub ruler: String,
+    pub culture: String,
+    pub personality: String,
+    pub traits: Vec<String>,
+    pub resources: Vec<(ResourceTypes, isize)>,
+}
+
+impl KingdomGenerator {
+    pub fn culture(&self, name: &str) -> Option<&CultureDef> {
+        self.cultures.iter().find(|culture| culture.name == name)
+    }
+
+    fn pick<'a, R: Rng>(options: &'a [String], rng: &mut R) -> &'a str {
+        options.choose(rng).map_or("", |option| option.as_str())
+    }
+
+    // Draws names until one isn't `taken`.
+    fn name<R: Rng>(&self, taken: &[String], rng: &mut R) -> String {
+        for _ in 0..NAME_ATTEMPTS {
+            let name = format!(
+                "{}{}",
+                KingdomGenerator::pick(&self.prefixes, rng),
+                KingdomGenerator::pick(&self.suffixes, rng)
+            );
+            if !name.is_empty() && !taken.contains(&name) {
+                return name;
+            }
+        }
+        return format!("Kingdom {}", taken.len() + 1);
+    }
+
+    pub fn generate<R: Rng>(
+        &self,
+        taken: &[String],
+        personalities: &[PersonalityDef],
+        rng: &mut R,
+    ) -> GeneratedKingdom {
+        let name = self.name(taken, rng);
+        let ruler = format!(
+            "{} {}",
+            KingdomGenerator::pick(&self.ruler_titles, rng),
+            KingdomGenerator::pick(&self.ruler_names, rng)
+        )
+        .trim()
+        .to_string();
+        let culture = self.cultures.choose(rng);
+        let personality = personalities
+            .choose(rng)
+            .map_or(String::new(), |personality| personality.name.clone());
+        let resources = STARTING_RESOURCES
+            .iter()
+            .map(|resource| {
+                let leaning: isize = culture.map_or(0, |culture| {
+                    culture
+                        .resources
+                        .iter()
+                        .filter(|(r, _)| r == resource)
+                        .map(|(_, amount)| *amount)
+                        .sum()
+                });
+                let roll = if self.spread > 0 {
+                    rng.gen_range(-self.spread..=self.spread)
+                } else {
+                    0
+                };
+                (*resource, (self.base + leaning + roll).max(0))
+            })
+            .collect();
+        return GeneratedKingdom {
+            name,
+            ruler,
+            culture: culture.map_or(String::new(), |culture| culture.name.clone()),
+            personality,
+            traits: culture.map_or(Vec::new(), |culture| culture.traits.clone()),
+            resources,
+        };
+    }
+
+    // Rolls `count` kingdoms (at least one) with distinct names, in ID order.
+    pub fn generate_world<R: Rng>(
+        &self,
+        count: usize,
+        personalities: &[PersonalityDef],
+        rng: &mut R,
+    ) -> Vec<GeneratedKingdom> {
+        let mut kingdoms: Vec<GeneratedKingdom> = Vec::new();
+        for _ in 0..count.max(1) {
+            let names: Vec<String> = kingdoms
+                .iter()
+                .map(|kingdom| kingdom.name.clone())
+                .collect();
+            kingdoms.push(self.generate(&names, personalities, rng));
+        }
+        return kingdoms;
+    }
+}
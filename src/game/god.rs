@@ -0,0 +1,240 @@
+use bevy::ecs::event::Events;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use strum_macros::{AsRefStr, EnumIter};
+
+use super::kingdom::{Kingdom, KingdomID};
+use super::resource::{Resource, ResourceType, ResourceTypes};
+use super::rivals::Devotion;
+use super::{ActionSource, Log, ResourceAlterationEvent};
+
+// Every turn, each kingdom tithes its Faith divided by this to the god.
+pub const TITHE_DIVISOR: isize = 25;
+// Each level of `Upgrade::MiracleDiscount` takes this much off the price of miracles.
+pub const MIRACLE_DISCOUNT_PERCENT: isize = 25;
+
+// The god's own progression. `faith` is the pool upgrades and miracles are paid from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GodStats {
+    pub faith: isize,
+    pub click_power: usize,
+    pub extra_targets: usize,
+    pub miracle_discount: usize,
+    pub passive_income: usize,
+    // Turns left before each miracle can be cast again. Miracles that are ready aren't listed.
+    #[serde(default)]
+    pub cooldowns: Vec<(String, usize)>,
+}
+
+impl Default for GodStats {
+    fn default() -> Self {
+        GodStats {
+            faith: 0,
+            click_power: 1,
+            extra_targets: 0,
+            miracle_discount: 0,
+            passive_income: 0,
+            cooldowns: Vec::new(),
+        }
+    }
+}
+
+impl GodStats {
+    pub fn miracle_cost(&self, base: isize) -> isize {
+        let percent = (100 - MIRACLE_DISCOUNT_PERCENT * self.miracle_discount as isize).max(0);
+        return base * percent / 100;
+    }
+
+    pub fn can_afford(&self, cost: isize) -> bool {
+        self.faith >= cost
+    }
+
+    pub fn cooldown(&self, miracle: &str) -> usize {
+        self.cooldowns
+            .iter()
+            .find(|(name, _)| name == miracle)
+            .map_or(0, |(_, turns)| *turns)
+    }
+
+    pub fn start_cooldown(&mut self, miracle: &str, turns: usize) {
+        self.cooldowns.retain(|(name, _)| name != miracle);
+        if turns > 0 {
+            self.cooldowns.push((miracle.to_string(), turns));
+        }
+    }
+
+    fn tick_cooldowns(&mut self) {
+        for (_, turns) in self.cooldowns.iter_mut() {
+            *turns -= 1;
+        }
+        self.cooldowns.retain(|(_, turns)| *turns > 0);
+    }
+
+    // Scales one of the player's blessings by click power, spreads it to extra kingdoms (taken in
+    // ID order after the one that was blessed) and discounts its cost.
+    pub fn empower(
+        &self,
+        action: ResourceAlterationEvent,
+        kingdoms: &[KingdomID],
+    ) -> ResourceAlterationEvent {
+        let mut kingdoms = kingdoms.to_vec();
+        kingdoms.sort_by_key(|KingdomID(id)| *id);
+        let mut empowered = Vec::new();
+        for (kingdom, effect) in action.changes.iter() {
+            let effect = effect.amplified(self.click_power as isize);
+            let start = match kingdoms.iter().position(|id| id == kingdom) {
+                Some(start) => start,
+                None => {
+                    empowered.push((*kingdom, effect));
+                    continue;
+                }
+            };
+            let reach = self.extra_targets.min(kingdoms.len() - 1);
+            for offset in 0..=reach {
+                empowered.push((kingdoms[(start + offset) % kingdoms.len()], effect.clone()));
+            }
+        }
+        return ResourceAlterationEvent {
+            changes: empowered,
+            cost: self.miracle_cost(action.cost),
+            ..action
+        };
+    }
+}
+
+#[derive(Debug, EnumIter, AsRefStr, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Upgrade {
+    ClickPower,
+    MultiTarget,
+    MiracleDiscount,
+    PassiveIncome,
+}
+
+impl Upgrade {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Upgrade::ClickPower => "Mighty Hand",
+            Upgrade::MultiTarget => "Wide Blessings",
+            Upgrade::MiracleDiscount => "Humble Miracles",
+            Upgrade::PassiveIncome => "Eternal Hymns",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Upgrade::ClickPower => "Your blessings grow stronger.",
+            Upgrade::MultiTarget => "Your blessings reach one more kingdom.",
+            Upgrade::MiracleDiscount => "Miracles cost a quarter less.",
+            Upgrade::PassiveIncome => "Gain one more faith every turn.",
+        }
+    }
+
+    pub fn level(&self, god: &GodStats) -> usize {
+        match self {
+            Upgrade::ClickPower => god.click_power.saturating_sub(1),
+            Upgrade::MultiTarget => god.extra_targets,
+            Upgrade::MiracleDiscount => god.miracle_discount,
+            Upgrade::PassiveIncome => god.passive_income,
+        }
+    }
+
+    pub fn max_level(&self) -> Option<usize> {
+        match self {
+            Upgrade::MultiTarget => Some(3),
+            Upgrade::MiracleDiscount => Some(3),
+            _ => None,
+        }
+    }
+
+    // `None` once the upgrade is maxed out.
+    pub fn cost(&self, god: &GodStats) -> Option<isize> {
+        let level = self.level(god);
+        if self.max_level().map_or(false, |max| level >= max) {
+            return None;
+        }
+        let base = match self {
+            Upgrade::ClickPower => 10,
+            Upgrade::MultiTarget => 30,
+            Upgrade::MiracleDiscount => 15,
+            Upgrade::PassiveIncome => 10,
+        };
+        return Some(base * (level as isize + 1));
+    }
+
+    fn apply(&self, god: &mut GodStats) {
+        match self {
+            Upgrade::ClickPower => god.click_power += 1,
+            Upgrade::MultiTarget => god.extra_targets += 1,
+            Upgrade::MiracleDiscount => god.miracle_discount += 1,
+            Upgrade::PassiveIncome => god.passive_income += 1,
+        }
+    }
+}
+
+// Buying an upgrade is the god's action for the turn.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PurchaseUpgradeEvent(pub Upgrade);
+
+// Runs first in `TurnState::CountingChanges`, before the turn's actions are recorded. Every change
+// waiting here was sent since the last tally, and the player's are only passed on once paid for,
+// so faith can't go below zero.
+pub fn spend_faith(
+    mut god: ResMut<GodStats>,
+    mut ev_resource_changes: ResMut<Events<ResourceAlterationEvent>>,
+    mut ev_purchase: EventReader<PurchaseUpgradeEvent>,
+    mut log: ResMut<Log>,
+) {
+    let actions: Vec<ResourceAlterationEvent> = ev_resource_changes.drain().collect();
+    for action in actions {
+        if action.source == ActionSource::Player {
+            if !god.can_afford(action.cost) {
+                log.0.push((
+                    "Your blessing falters for lack of faith.".to_string(),
+                    format!("{} faith needed", action.cost),
+                ));
+                continue;
+            }
+            god.faith -= action.cost;
+        }
+        ev_resource_changes.send(action);
+    }
+    for PurchaseUpgradeEvent(upgrade) in ev_purchase.iter() {
+        match upgrade.cost(&god) {
+            Some(cost) if god.can_afford(cost) => {
+                god.faith -= cost;
+                upgrade.apply(&mut god);
+                log.0.push((
+                    format!("You grow in power: {}.", upgrade.name()),
+                    format!("-{} faith", cost),
+                ));
+            }
+            Some(_) => log.0.push((
+                format!("You lack the faith for {}.", upgrade.name()),
+                String::new(),
+            )),
+            None => {}
+        }
+    }
+}
+
+// Runs at the end of `TurnState::ApplyingChanges`, so the tithe reflects this turn's Faith. Faith
+// devoted to rival gods tithes to them instead. Miracles also come a turn closer to being ready.
+pub fn collect_tithe(
+    mut god: ResMut<GodStats>,
+    resource_query: Query<(&Resource, &ResourceType, &KingdomID)>,
+    devotion_query: Query<(&KingdomID, &Devotion), With<Kingdom>>,
+) {
+    let tithe: isize = resource_query
+        .iter()
+        .filter(|(_, ResourceType(resource_type), _)| *resource_type == ResourceTypes::Faith)
+        .map(|(resource, _, kingdom)| {
+            let devoted = devotion_query
+                .iter()
+                .find(|(id, _)| *id == kingdom)
+                .map_or(0, |(_, devotion)| devotion.total());
+            (resource.value - devoted).max(0) / TITHE_DIVISOR
+        })
+        .sum();
+    god.faith += tithe + god.passive_income as isize;
+    god.tick_cooldowns();
+}
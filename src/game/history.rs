@@ -0,0 +1,99 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use super::save::{GameSnapshot, LoadGameEvent, SnapshotParams};
+use super::Turn;
+
+pub const DEFAULT_HISTORY_LIMIT: usize = 50;
+
+// Snapshots taken at the start of each turn, oldest first.
+pub struct TurnHistory {
+    pub limit: usize,
+    snapshots: VecDeque<GameSnapshot>,
+    rewinding_to: Option<usize>,
+}
+
+impl Default for TurnHistory {
+    fn default() -> Self {
+        TurnHistory {
+            limit: DEFAULT_HISTORY_LIMIT,
+            snapshots: VecDeque::new(),
+            rewinding_to: None,
+        }
+    }
+}
+
+impl TurnHistory {
+    pub fn push(&mut self, snapshot: GameSnapshot) {
+        self.snapshots.push_back(snapshot);
+        while self.snapshots.len() > self.limit.max(1) {
+            self.snapshots.pop_front();
+        }
+    }
+
+    pub fn latest(&self) -> Option<&GameSnapshot> {
+        self.snapshots.back()
+    }
+
+    pub fn get(&self, turn: usize) -> Option<&GameSnapshot> {
+        self.snapshots.iter().find(|snapshot| snapshot.turn == turn)
+    }
+
+    pub fn turns(&self) -> Vec<usize> {
+        self.snapshots
+            .iter()
+            .map(|snapshot| snapshot.turn)
+            .collect()
+    }
+
+    // Whether the snapshot being restored for `turn` came from a rewind rather than a load.
+    pub fn finish_rewind(&mut self, turn: usize) -> bool {
+        self.rewinding_to.take() == Some(turn)
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+    }
+
+    // Drops every snapshot after `turn`, leaving `turn` as the latest.
+    fn truncate_after(&mut self, turn: usize) {
+        self.snapshots.retain(|snapshot| snapshot.turn <= turn);
+    }
+}
+
+pub struct UndoEvent;
+
+pub struct RewindEvent(pub usize);
+
+pub fn record_history(snapshot: SnapshotParams, mut history: ResMut<TurnHistory>) {
+    history.push(snapshot.capture());
+}
+
+// Runs in `CoreStage::PreUpdate` ahead of `restore_snapshot`, which does the actual restoring.
+pub fn rewind(
+    mut ev_undo: EventReader<UndoEvent>,
+    mut ev_rewind: EventReader<RewindEvent>,
+    mut ev_load: EventWriter<LoadGameEvent>,
+    mut history: ResMut<TurnHistory>,
+    turn: Res<Turn>,
+) {
+    let mut target: Option<usize> = None;
+    for _ in ev_undo.iter() {
+        target = Some(target.unwrap_or(turn.0).saturating_sub(1));
+    }
+    for RewindEvent(rewind_turn) in ev_rewind.iter() {
+        target = Some(*rewind_turn);
+    }
+
+    if let Some(target) = target {
+        match history.get(target).cloned() {
+            Some(snapshot) => {
+                history.truncate_after(target);
+                history.rewinding_to = Some(target);
+                ev_load.send(LoadGameEvent(snapshot));
+            }
+            None => warn!("Turn {} is no longer in the history.", target),
+        }
+    }
+}
@@ -1,15 +1,58 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 // use std::cmp::{max, min};
-use super::resource::{Resource, ResourceType, ResourceTypes, STARTING_RESOURCES};
+use super::ai::{KingdomAi, Personality};
+use super::effect::Effect;
+use super::generation::KingdomGenerator;
+use super::resource::{Modifiers, Resource, ResourceType, ResourceTypes};
+use super::rivals::Devotion;
+use super::rng::{GameRng, RngStream};
+use super::task::spawn_starting_tasks;
+use super::traits::Traits;
+use super::unrest::Unrest;
 
 pub struct KingdomPlugin;
 
 #[derive(Debug, Component)]
 pub struct Kingdom;
 
-#[derive(Debug, Component, Default)]
+#[derive(Debug, Component, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct KingdomID(pub usize);
 
+// A permanent effect applied to the kingdom at the start of every tally.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Boon {
+    pub source: String,
+    pub effect: Effect,
+}
+
+#[derive(Debug, Component, Clone, Default)]
+pub struct Boons(pub Vec<Boon>);
+
+// Names of locked blessings the god may now cast on this kingdom.
+#[derive(Debug, Component, Clone, Default)]
+pub struct UnlockedBlessings(pub Vec<String>);
+
+// Who rules the kingdom, with their title.
+#[derive(Debug, Component, Clone, Default)]
+pub struct Ruler(pub String);
+
+// The name of one of the cultures in generation.ron.
+#[derive(Debug, Component, Clone, Default)]
+pub struct Culture(pub String);
+
+// How a new game's world is set up.
+#[derive(Debug, Clone)]
+pub struct WorldSettings {
+    pub kingdom_count: usize,
+}
+
+impl Default for WorldSettings {
+    fn default() -> Self {
+        WorldSettings { kingdom_count: 2 }
+    }
+}
+
 #[derive(Bundle)]
 struct KingdomBundle {
     name: Name,
@@ -22,28 +65,68 @@ struct KingdomBundle {
 
 impl Plugin for KingdomPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(setup_kingdoms);
+        app.init_resource::<WorldSettings>()
+            .add_startup_system(setup_kingdoms);
     }
 }
 
-fn setup_kingdoms(mut commands: Commands) {
-    for i in 1..=2 {
+// Every kingdom is rolled from the game's seed, so the same seed always makes the same world.
+fn setup_kingdoms(
+    mut commands: Commands,
+    ai: Res<KingdomAi>,
+    generator: Res<KingdomGenerator>,
+    settings: Res<WorldSettings>,
+    mut rng: ResMut<GameRng>,
+) {
+    let kingdoms = generator.generate_world(
+        settings.kingdom_count,
+        &ai.personalities,
+        rng.stream(RngStream::Generation),
+    );
+    for (i, generated) in kingdoms.into_iter().enumerate() {
+        let id = KingdomID(i + 1);
+        let kingdom = spawn_kingdom(&mut commands, id, generated.name, &generated.resources);
         commands
-            .spawn()
-            .insert(Name::new(format!("Kingdom {}", i).to_string()))
-            .with_children(|kingdom| {
-                for resource_type in STARTING_RESOURCES.iter() {
-                    kingdom
-                        .spawn()
-                        .insert(Resource {
-                            value: 50,
-                            change: 0,
-                        })
-                        .insert(ResourceType(*resource_type))
-                        .insert(KingdomID(i));
-                }
-            })
-            .insert(KingdomID(i))
-            .insert(Kingdom);
+            .entity(kingdom)
+            .insert(Ruler(generated.ruler))
+            .insert(Culture(generated.culture))
+            .insert(Personality(generated.personality))
+            .insert(Traits(generated.traits));
+        spawn_starting_tasks(&mut commands, id);
     }
 }
+
+pub fn spawn_kingdom(
+    commands: &mut Commands,
+    id: KingdomID,
+    name: String,
+    resources: &[(ResourceTypes, isize)],
+) -> Entity {
+    commands
+        .spawn()
+        .insert(Name::new(name))
+        .with_children(|kingdom| {
+            for (resource_type, value) in resources.iter() {
+                kingdom
+                    .spawn()
+                    .insert(Resource {
+                        value: *value,
+                        change: 0,
+                    })
+                    .insert(ResourceType(*resource_type))
+                    .insert(id);
+            }
+        })
+        .insert(id)
+        .insert(Kingdom)
+        .insert(Personality::default())
+        .insert(Ruler::default())
+        .insert(Culture::default())
+        .insert(Traits::default())
+        .insert(Devotion::default())
+        .insert(Unrest::default())
+        .insert(Boons::default())
+        .insert(Modifiers::default())
+        .insert(UnlockedBlessings::default())
+        .id()
+}
@@ -0,0 +1,312 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::diplomacy::Relations;
+use super::effect::{describe_all, Condition, Effect};
+use super::god::GodStats;
+use super::kingdom::{Kingdom, KingdomID};
+use super::resource::{KingdomResources, Resource, ResourceType, ResourceTypes};
+use super::rivals::Devotion;
+use super::{kingdom_snapshot, ActionSource, Log, ResourceAlterationEvent};
+
+const MIRACLES_DATA: &str = include_str!("../../assets/data/miracles.ron");
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum KingdomTarget {
+    #[default]
+    One,
+    All,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ResourceTarget {
+    // The effects as written.
+    #[default]
+    Fixed,
+    // Every effect aimed at the resource the god picks.
+    Chosen,
+    // Every effect repeated for each listed resource.
+    Each(Vec<ResourceTypes>),
+}
+
+// How a kingdom takes being punished. It accepts the punishment as just when it has sinned: when
+// it is at war, has given most of its Faith to rival gods, or meets any of `sins`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Wrath {
+    #[serde(default)]
+    pub sins: Vec<Condition>,
+    pub deserved: Vec<Effect>,
+    pub undeserved: Vec<Effect>,
+}
+
+impl Wrath {
+    pub fn is_deserved(&self, resources: &KingdomResources, devoted: isize, at_war: bool) -> bool {
+        at_war
+            || devoted * 2 > resources.get(ResourceTypes::Faith)
+            || self.sins.iter().any(|sin| sin.holds(resources))
+    }
+
+    pub fn judgement(
+        &self,
+        kingdom: KingdomID,
+        name: &str,
+        deserved: bool,
+    ) -> ResourceAlterationEvent {
+        let (message, effects) = if deserved {
+            (
+                format!("{} bows its head, knowing its punishment was just.", name),
+                &self.deserved,
+            )
+        } else {
+            (
+                format!("{} cries out against your cruelty.", name),
+                &self.undeserved,
+            )
+        };
+        return ResourceAlterationEvent {
+            source: ActionSource::Wrath,
+            message,
+            changes: effects
+                .iter()
+                .map(|effect| (kingdom, effect.clone()))
+                .collect(),
+            cost: 0,
+        };
+    }
+
+    pub fn describe(&self) -> String {
+        let mut sins = vec!["at war".to_string(), "devoted to rival gods".to_string()];
+        sins.extend(self.sins.iter().map(|sin| sin.describe()));
+        return format!(
+            "Deserved when {}: {}\nOtherwise: {}",
+            sins.join(" or "),
+            describe_all(&self.deserved),
+            describe_all(&self.undeserved)
+        );
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MiracleDef {
+    pub name: String,
+    pub description: String,
+    // `{kingdom}` and `{resource}` are replaced with the targets.
+    pub message: String,
+    // Faith the god pays to cast it, before any discount.
+    pub cost: isize,
+    // Turns before it can be cast again.
+    pub cooldown: usize,
+    #[serde(default)]
+    pub kingdoms: KingdomTarget,
+    #[serde(default)]
+    pub resources: ResourceTarget,
+    pub effects: Vec<Effect>,
+    // Punishments also carry the kingdom's verdict on them.
+    #[serde(default)]
+    pub wrath: Option<Wrath>,
+}
+
+impl MiracleDef {
+    // Whether casting it needs a kingdom, a resource or both picked first.
+    pub fn needs_target(&self) -> bool {
+        self.kingdoms == KingdomTarget::One || self.resources == ResourceTarget::Chosen
+    }
+
+    // Off cooldown and within the god's means.
+    pub fn ready(&self, god: &GodStats) -> bool {
+        god.cooldown(&self.name) == 0 && god.can_afford(god.miracle_cost(self.cost))
+    }
+
+    pub fn describe(&self) -> String {
+        let kingdoms = match self.kingdoms {
+            KingdomTarget::One => "one kingdom",
+            KingdomTarget::All => "every kingdom",
+        };
+        let effects = match &self.resources {
+            ResourceTarget::Fixed => describe_all(&self.effects),
+            ResourceTarget::Chosen => format!("{} (any resource)", describe_all(&self.effects)),
+            ResourceTarget::Each(resources) => resources
+                .iter()
+                .map(|resource| describe_all(&self.retargeted(*resource)))
+                .collect::<Vec<String>>()
+                .join(", "),
+        };
+        let description = format!(
+            "{} ({} faith, every {} turns, {}): {}\n{}",
+            self.name, self.cost, self.cooldown, kingdoms, effects, self.description
+        );
+        return match &self.wrath {
+            Some(wrath) => format!("{}\n{}", description, wrath.describe()),
+            None => description,
+        };
+    }
+
+    fn retargeted(&self, resource: ResourceTypes) -> Vec<Effect> {
+        self.effects
+            .iter()
+            .map(|effect| effect.retargeted(resource))
+            .collect()
+    }
+
+    // The kingdoms the miracle lands on, or `None` when it needs one and none was picked.
+    pub fn targets(
+        &self,
+        kingdom: Option<KingdomID>,
+        kingdoms: &[KingdomID],
+    ) -> Option<Vec<KingdomID>> {
+        match self.kingdoms {
+            KingdomTarget::One => Some(vec![kingdom?]),
+            KingdomTarget::All => Some(kingdoms.to_vec()),
+        }
+    }
+
+    // The changes the miracle makes to `kingdoms`, or `None` when a target it needs is missing.
+    pub fn alteration(
+        &self,
+        kingdom: Option<KingdomID>,
+        resource: Option<ResourceTypes>,
+        kingdoms: &[KingdomID],
+        kingdom_name: &str,
+    ) -> Option<ResourceAlterationEvent> {
+        let targets = self.targets(kingdom, kingdoms)?;
+        let effects: Vec<Effect> = match &self.resources {
+            ResourceTarget::Fixed => self.effects.clone(),
+            ResourceTarget::Chosen => self.retargeted(resource?),
+            ResourceTarget::Each(resources) => resources
+                .iter()
+                .flat_map(|resource| self.retargeted(*resource))
+                .collect(),
+        };
+        let resource_name =
+            resource.map_or(String::new(), |resource| resource.as_ref().to_string());
+        let source = match self.wrath {
+            Some(_) => ActionSource::Wrath,
+            None => ActionSource::Miracle,
+        };
+        return Some(ResourceAlterationEvent {
+            source,
+            message: self
+                .message
+                .replace("{kingdom}", kingdom_name)
+                .replace("{resource}", &resource_name),
+            changes: targets
+                .iter()
+                .flat_map(|kingdom| effects.iter().map(move |effect| (*kingdom, effect.clone())))
+                .collect(),
+            cost: self.cost,
+        });
+    }
+}
+
+pub struct Miracles(pub Vec<MiracleDef>);
+
+impl Default for Miracles {
+    fn default() -> Self {
+        Miracles(ron::from_str(MIRACLES_DATA).expect("Could not parse miracles.ron"))
+    }
+}
+
+impl Miracles {
+    pub fn get(&self, name: &str) -> Option<&MiracleDef> {
+        self.0.iter().find(|miracle| miracle.name == name)
+    }
+}
+
+// Casting a miracle is the god's action for the turn. `kingdom` and `resource` are only needed
+// by miracles that target them.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MiracleEvent {
+    pub miracle: String,
+    #[serde(default)]
+    pub kingdom: Option<KingdomID>,
+    #[serde(default)]
+    pub resource: Option<ResourceTypes>,
+}
+
+// What the kingdoms a miracle lands on look like before it strikes.
+#[derive(SystemParam)]
+pub struct MiracleTargets<'w, 's> {
+    relations: Res<'w, Relations>,
+    kingdom_query:
+        Query<'w, 's, (&'static KingdomID, &'static Name, &'static Devotion), With<Kingdom>>,
+    resource_query: Query<'w, 's, (&'static Resource, &'static ResourceType, &'static KingdomID)>,
+}
+
+// Runs during `TurnState::CountingChanges`, once the turn's actions have been recorded. Miracles
+// are paid for here, as they are cast. Each kingdom judges a punishment by how it stood before
+// being struck.
+pub fn cast_miracles(
+    miracles: Res<Miracles>,
+    mut god: ResMut<GodStats>,
+    targets: MiracleTargets,
+    mut ev_miracle: EventReader<MiracleEvent>,
+    mut ev_resource_changes: EventWriter<ResourceAlterationEvent>,
+    mut log: ResMut<Log>,
+) {
+    let mut kingdoms: Vec<KingdomID> = targets.kingdom_query.iter().map(|(id, _, _)| *id).collect();
+    kingdoms.sort_by_key(|KingdomID(id)| *id);
+    for event in ev_miracle.iter() {
+        let miracle = match miracles.get(&event.miracle) {
+            Some(miracle) => miracle,
+            None => continue,
+        };
+        let cost = god.miracle_cost(miracle.cost);
+        if god.cooldown(&miracle.name) > 0 {
+            log.0.push((
+                format!("{} is not ready yet.", miracle.name),
+                format!("{} turns left", god.cooldown(&miracle.name)),
+            ));
+            continue;
+        }
+        if !god.can_afford(cost) {
+            log.0.push((
+                format!("You lack the faith for {}.", miracle.name),
+                String::new(),
+            ));
+            continue;
+        }
+        let kingdom_name = event
+            .kingdom
+            .and_then(|kingdom| {
+                targets
+                    .kingdom_query
+                    .iter()
+                    .find(|(id, _, _)| **id == kingdom)
+            })
+            .map_or(String::new(), |(_, name, _)| name.to_string());
+        match miracle.alteration(event.kingdom, event.resource, &kingdoms, &kingdom_name) {
+            Some(alteration) => {
+                god.faith -= cost;
+                god.start_cooldown(&miracle.name, miracle.cooldown);
+                ev_resource_changes.send(ResourceAlterationEvent { cost, ..alteration });
+                if let Some(wrath) = &miracle.wrath {
+                    let struck = miracle
+                        .targets(event.kingdom, &kingdoms)
+                        .unwrap_or_default();
+                    for target in struck.iter() {
+                        let (kingdom, name, devotion) = match targets
+                            .kingdom_query
+                            .iter()
+                            .find(|(id, _, _)| *id == target)
+                        {
+                            Some(found) => found,
+                            None => continue,
+                        };
+                        let resources = kingdom_snapshot(*kingdom, targets.resource_query.iter());
+                        let at_war = targets.relations.of(*kingdom).any(|relation| relation.war);
+                        let deserved = wrath.is_deserved(&resources, devotion.total(), at_war);
+                        ev_resource_changes.send(wrath.judgement(
+                            *kingdom,
+                            &name.to_string(),
+                            deserved,
+                        ));
+                    }
+                }
+            }
+            None => log
+                .0
+                .push((format!("{} needs a target.", miracle.name), String::new())),
+        }
+    }
+}
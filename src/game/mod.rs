@@ -1,20 +1,88 @@
+pub mod ai;
+pub mod blessing;
+pub mod diplomacy;
+pub mod economy;
+pub mod effect;
+pub mod events;
+pub mod generation;
+pub mod god;
+pub mod history;
 pub mod kingdom;
+pub mod miracle;
+pub mod outcome;
+pub mod replay;
 pub mod resource;
+pub mod rivals;
+pub mod rng;
+pub mod save;
+pub mod simulation;
 pub mod task;
+pub mod traits;
+pub mod unrest;
+pub mod war;
 
+use bevy::ecs::event::Events;
 use bevy::{prelude::*, utils::hashbrown::HashMap};
+use serde::{Deserialize, Serialize};
 
+use self::ai::{kingdom_ai_turn, KingdomAi};
+use self::blessing::Blessings;
+use self::diplomacy::{sync_relations, update_relations, RelationShiftEvent, Relations};
+use self::economy::{run_economy, Economy};
+use self::effect::Effect;
+use self::events::{roll_kingdom_events, KingdomEvents};
+use self::generation::KingdomGenerator;
+use self::god::{collect_tithe, spend_faith, GodStats, PurchaseUpgradeEvent};
+use self::history::{record_history, rewind, RewindEvent, TurnHistory, UndoEvent};
 use self::kingdom::{Kingdom, KingdomID};
-use self::resource::{Resource, ResourceType};
+use self::miracle::{cast_miracles, MiracleEvent, Miracles};
+use self::outcome::{check_game_over, EndRules, GameOver, GameOverEvent};
+use self::replay::{
+    begin_recording, play_replay, record_actions, write_replay, ReplayPlayer, ReplayRecorder,
+};
+use self::resource::{
+    KingdomResources, Modifiers, Resource, ResourceBounds, ResourceType, ResourceTypes,
+};
+use self::rivals::{collect_rival_tithe, rival_gods_act, Pantheon, RivalGods};
+use self::rng::GameRng;
+use self::save::{
+    autosave, restore_snapshot, start_new_game, Autosave, GameLoadedEvent, LoadGameEvent,
+    NewGameEvent,
+};
+use self::task::{advance_tasks, apply_boons, TaskCompletedEvent, TaskProgressEvent};
+use self::traits::{KingdomTraits, Traits};
+use self::unrest::stir_unrest;
+use self::war::resolve_battles;
 
+// Who asked for a change. Only the player's actions are recorded in replays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ActionSource {
+    Player,
+    // Miracles the god cast. They are paid for as they are cast, and replays record the cast
+    // rather than what it did.
+    Miracle,
+    // The player's punishments, paid for like miracles.
+    Wrath,
+    // A rival god, by its place in the pantheon.
+    Rival(usize),
+    World,
+    // The per-turn economy rules.
+    Economy,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResourceAlterationEvent {
+    pub source: ActionSource,
     pub message: String,
-    pub changes: Vec<(Entity, fn(usize) -> usize)>,
+    pub changes: Vec<(KingdomID, Effect)>,
+    // Faith the god paid for the action.
+    #[serde(default)]
+    pub cost: isize,
 }
 
 pub struct GodActionEvent;
 
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub enum TurnState {
     WaitingForGod,
     CountingChanges,
@@ -23,44 +91,200 @@ pub enum TurnState {
 
 pub struct Log(pub Vec<(String, String)>);
 
+// Number of turns played so far.
+#[derive(Default)]
+pub struct Turn(pub usize);
+
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(Log(Vec::new()));
+        app.insert_resource(Log(Vec::new()))
+            .init_resource::<ResourceBounds>()
+            .init_resource::<Blessings>()
+            .init_resource::<Autosave>()
+            .init_resource::<Turn>()
+            .init_resource::<TurnHistory>()
+            .init_resource::<ReplayRecorder>()
+            .init_resource::<ReplayPlayer>()
+            .init_resource::<GameRng>()
+            .init_resource::<KingdomEvents>()
+            .init_resource::<GodStats>()
+            .init_resource::<Miracles>()
+            .init_resource::<Economy>()
+            .init_resource::<KingdomAi>()
+            .init_resource::<KingdomGenerator>()
+            .init_resource::<KingdomTraits>()
+            .init_resource::<EndRules>()
+            .init_resource::<Pantheon>()
+            .init_resource::<RivalGods>()
+            .init_resource::<GameOver>()
+            .init_resource::<Relations>();
 
         app.add_state(TurnState::WaitingForGod);
 
         app.add_event::<ResourceAlterationEvent>()
-            .add_event::<GodActionEvent>();
+            .add_event::<PurchaseUpgradeEvent>()
+            .add_event::<MiracleEvent>()
+            .add_event::<RelationShiftEvent>()
+            .add_event::<GodActionEvent>()
+            .add_event::<LoadGameEvent>()
+            .add_event::<GameLoadedEvent>()
+            .add_event::<NewGameEvent>()
+            .add_event::<GameOverEvent>()
+            .add_event::<UndoEvent>()
+            .add_event::<RewindEvent>()
+            .add_event::<TaskProgressEvent>()
+            .add_event::<TaskCompletedEvent>();
 
-        app.add_system(check_for_god_action);
+        app.add_system_to_stage(CoreStage::PreUpdate, rewind.label("rewind"))
+            .add_system_to_stage(CoreStage::PreUpdate, start_new_game.label("new_game"))
+            .add_system_to_stage(
+                CoreStage::PreUpdate,
+                restore_snapshot.after("rewind").after("new_game"),
+            );
+
+        app.add_system(check_for_god_action)
+            .add_system(play_replay)
+            .add_system_to_stage(CoreStage::PostUpdate, sync_relations);
         app.add_system_set(
+            SystemSet::on_enter(TurnState::CountingChanges)
+                .label("spend")
+                .with_system(spend_faith),
+        )
+        .add_system_set(
+            SystemSet::on_enter(TurnState::CountingChanges)
+                .label("record")
+                .with_system(record_actions)
+                .after("spend"),
+        )
+        .add_system_set(
+            SystemSet::on_enter(TurnState::CountingChanges)
+                .label("miracles")
+                .with_system(cast_miracles)
+                .after("record"),
+        )
+        .add_system_set(
             SystemSet::on_enter(TurnState::CountingChanges)
                 .label("clear")
-                .with_system(clear_change),
+                .with_system(clear_change)
+                .after("record"),
+        )
+        .add_system_set(
+            SystemSet::on_enter(TurnState::CountingChanges)
+                .label("events")
+                .with_system(roll_kingdom_events)
+                .after("clear")
+                .after("miracles"),
+        )
+        .add_system_set(
+            SystemSet::on_enter(TurnState::CountingChanges)
+                .label("rivals")
+                .with_system(rival_gods_act)
+                .after("events"),
+        )
+        .add_system_set(
+            SystemSet::on_enter(TurnState::CountingChanges)
+                .label("ai")
+                .with_system(kingdom_ai_turn)
+                .after("rivals"),
+        )
+        .add_system_set(
+            SystemSet::on_enter(TurnState::CountingChanges)
+                .label("diplomacy")
+                .with_system(update_relations)
+                .after("ai"),
+        )
+        .add_system_set(
+            SystemSet::on_enter(TurnState::CountingChanges)
+                .label("war")
+                .with_system(resolve_battles)
+                .after("diplomacy"),
+        )
+        .add_system_set(
+            SystemSet::on_enter(TurnState::CountingChanges)
+                .label("tasks")
+                .with_system(advance_tasks)
+                .after("war"),
+        )
+        .add_system_set(
+            SystemSet::on_enter(TurnState::CountingChanges)
+                .label("boons")
+                .with_system(apply_boons)
+                .after("tasks"),
+        )
+        .add_system_set(
+            SystemSet::on_enter(TurnState::CountingChanges)
+                .label("economy")
+                .with_system(run_economy)
+                .after("boons"),
         )
         .add_system_set(
             SystemSet::on_enter(TurnState::CountingChanges)
                 .label("count")
                 .with_system(tally_changes)
-                .after("clear"),
+                .after("economy"),
+        )
+        .add_system_set(
+            SystemSet::on_enter(TurnState::ApplyingChanges)
+                .label("modifiers")
+                .with_system(apply_modifiers),
         )
         .add_system_set(
             SystemSet::on_enter(TurnState::ApplyingChanges)
                 .label("apply")
-                .with_system(apply_changes),
+                .with_system(apply_changes)
+                .after("modifiers"),
+        )
+        .add_system_set(
+            SystemSet::on_enter(TurnState::ApplyingChanges)
+                .label("rival_tithe")
+                .with_system(collect_rival_tithe)
+                .after("apply"),
         )
         .add_system_set(
-            SystemSet::on_enter(TurnState::WaitingForGod).with_system(start_of_turn_log_edits),
+            SystemSet::on_enter(TurnState::ApplyingChanges)
+                .label("tithe")
+                .with_system(collect_tithe)
+                .after("rival_tithe"),
+        )
+        .add_system_set(
+            SystemSet::on_enter(TurnState::ApplyingChanges)
+                .label("unrest")
+                .with_system(stir_unrest)
+                .after("tithe"),
+        )
+        .add_system_set(
+            SystemSet::on_enter(TurnState::WaitingForGod)
+                .label("turn_start")
+                .with_system(start_of_turn_log_edits),
+        )
+        .add_system_set(
+            SystemSet::on_enter(TurnState::WaitingForGod)
+                .label("game_over")
+                .with_system(check_game_over)
+                .after("turn_start"),
+        )
+        .add_system_set(
+            SystemSet::on_enter(TurnState::WaitingForGod)
+                .after("game_over")
+                .with_system(record_history)
+                .with_system(autosave)
+                .with_system(begin_recording)
+                .with_system(write_replay),
         );
     }
 }
 
 fn check_for_god_action(
     mut ev_godaction: EventReader<GodActionEvent>,
+    game_over: Res<GameOver>,
     mut state: ResMut<State<TurnState>>,
 ) {
+    // Once the game has ended, nothing more happens until a new one is loaded.
+    if game_over.0.is_some() {
+        return;
+    }
     match ev_godaction.iter().next() {
         Some(_) => state.set(TurnState::CountingChanges).unwrap(),
         None => {}
@@ -68,30 +292,71 @@ fn check_for_god_action(
 }
 
 fn clear_change(mut resource_query: Query<&mut Resource>) {
-    println!("Clearing changes.");
+    info!("Clearing changes.");
     for mut resource in resource_query.iter_mut() {
         resource.change = 0;
     }
 }
 
+// Kingdom traits scale each change as it is counted, so a blessing or rule can be worth more to
+// one kingdom than another. Counted changes are cleared out, so only actions sent after this turn
+// reach the next one.
 fn tally_changes(
     mut state: ResMut<State<TurnState>>,
-    mut resource_query: Query<(&mut Resource, &ResourceType)>,
-    mut ev_resource_changes: EventReader<ResourceAlterationEvent>,
+    kingdom_traits: Res<KingdomTraits>,
+    mut resource_query: Query<(&mut Resource, &ResourceType, &KingdomID)>,
+    mut modifier_query: Query<(&KingdomID, &mut Modifiers), With<Kingdom>>,
+    trait_query: Query<(&KingdomID, &Traits), With<Kingdom>>,
+    mut ev_resource_changes: ResMut<Events<ResourceAlterationEvent>>,
     mut log: ResMut<Log>,
 ) {
-    for ResourceAlterationEvent { message, changes } in ev_resource_changes.iter() {
+    let no_traits = Traits::default();
+    let counted: Vec<ResourceAlterationEvent> = ev_resource_changes.drain().collect();
+    for ResourceAlterationEvent {
+        source,
+        message,
+        changes,
+        ..
+    } in counted.iter()
+    {
         let mut alteration_outcomes: Vec<String> = Vec::new();
-        for (entity, change) in changes {
-            let (mut resource, ResourceType(resource_type)) =
-                resource_query.get_mut(*entity).unwrap();
-            let change = change(resource.value) - resource.value;
-            (*resource).change += change;
-            alteration_outcomes.push(format!(
-                "{} to {}",
-                change,
-                resource_type.as_ref().to_string()
-            ));
+        for (kingdom, effect) in changes {
+            let before = kingdom_snapshot(*kingdom, resource_query.iter());
+            let mut after = before.clone();
+            effect.apply(&mut after);
+            let traits = trait_query
+                .iter()
+                .find(|(id, _)| *id == kingdom)
+                .map_or(&no_traits, |(_, traits)| traits);
+            for (mut resource, ResourceType(resource_type), resource_kingdom) in
+                resource_query.iter_mut()
+            {
+                if resource_kingdom != kingdom {
+                    continue;
+                }
+                let change = kingdom_traits.scale(
+                    traits,
+                    *source,
+                    *resource_type,
+                    after.get(*resource_type) - before.get(*resource_type),
+                );
+                if change != 0 {
+                    (*resource).change += change;
+                    alteration_outcomes.push(format!(
+                        "{:+} to {}",
+                        change,
+                        resource_type.as_ref().to_string()
+                    ));
+                }
+            }
+            for modifier in effect.modifiers(&before) {
+                alteration_outcomes.push(modifier.describe());
+                for (modifier_kingdom, mut modifiers) in modifier_query.iter_mut() {
+                    if modifier_kingdom == kingdom {
+                        modifiers.0.push(modifier.clone());
+                    }
+                }
+            }
         }
         log.0
             .push((message.clone().to_string(), alteration_outcomes.join(", ")));
@@ -99,10 +364,105 @@ fn tally_changes(
     state.set(TurnState::ApplyingChanges);
 }
 
-fn apply_changes(mut state: ResMut<State<TurnState>>, mut resource_query: Query<&mut Resource>) {
-    for mut resource in resource_query.iter_mut() {
-        resource.value += resource.change;
+// Builds a kingdom's resources as they will stand once the changes tallied so far are applied.
+pub fn kingdom_snapshot<'a>(
+    kingdom: KingdomID,
+    resources: impl Iterator<Item = (&'a Resource, &'a ResourceType, &'a KingdomID)>,
+) -> KingdomResources {
+    let mut snapshot = KingdomResources::new();
+    for (resource, ResourceType(resource_type), resource_kingdom) in resources {
+        if *resource_kingdom == kingdom {
+            snapshot.set(*resource_type, resource.value + resource.change);
+        }
+    }
+    return snapshot;
+}
+
+// Runs first in `TurnState::ApplyingChanges`, once every change for the turn has been tallied.
+fn apply_modifiers(
+    mut kingdom_query: Query<(&KingdomID, &Name, &mut Modifiers), With<Kingdom>>,
+    mut resource_query: Query<(&mut Resource, &ResourceType, &KingdomID)>,
+    mut log: ResMut<Log>,
+) {
+    let mut kingdoms: Vec<(&KingdomID, &Name, Mut<Modifiers>)> = kingdom_query.iter_mut().collect();
+    kingdoms.sort_by_key(|(KingdomID(id), _, _)| *id);
+    for (kingdom, name, mut modifiers) in kingdoms {
+        if modifiers.0.is_empty() {
+            continue;
+        }
+        let mut outcomes: Vec<(ResourceTypes, String)> = Vec::new();
+        for (mut resource, ResourceType(resource_type), resource_kingdom) in
+            resource_query.iter_mut()
+        {
+            if resource_kingdom != kingdom {
+                continue;
+            }
+            let modified = modifiers.modify(*resource_type, resource.change);
+            if modified != resource.change {
+                outcomes.push((
+                    *resource_type,
+                    format!(
+                        "{:+} to {}",
+                        modified - resource.change,
+                        resource_type.as_ref()
+                    ),
+                ));
+                resource.change = modified;
+            }
+        }
+        if !outcomes.is_empty() {
+            outcomes.sort_by_key(|(resource_type, _)| *resource_type as usize);
+            log.0.push((
+                format!("Blessings and curses linger over {}.", name),
+                outcomes
+                    .into_iter()
+                    .map(|(_, outcome)| outcome)
+                    .collect::<Vec<String>>()
+                    .join(", "),
+            ));
+        }
+        modifiers.tick();
+    }
+}
+
+fn apply_changes(
+    mut state: ResMut<State<TurnState>>,
+    mut resource_query: Query<(&mut Resource, &ResourceType, &KingdomID)>,
+    kingdom_query: Query<(&KingdomID, &Name), With<Kingdom>>,
+    bounds: Res<ResourceBounds>,
+    mut log: ResMut<Log>,
+    mut turn: ResMut<Turn>,
+) {
+    for (mut resource, ResourceType(resource_type), KingdomID(id)) in resource_query.iter_mut() {
+        let (min, max) = bounds.get(*resource_type);
+        let target = resource.value.saturating_add(resource.change);
+        let clamped = target.clamp(min, max);
+        if clamped != target {
+            let kingdom_name = kingdom_query
+                .iter()
+                .find(|(KingdomID(kingdom_id), _)| kingdom_id == id)
+                .map(|(_, name)| name.to_string())
+                .unwrap_or_else(|| format!("Kingdom {}", id));
+            let limit = if clamped == min { "below" } else { "above" };
+            log.0.push((
+                format!(
+                    "{}'s {} cannot go {} {}.",
+                    kingdom_name,
+                    resource_type.as_ref(),
+                    limit,
+                    clamped
+                ),
+                format!(
+                    "{} of the {} change was lost.",
+                    (target - clamped).abs(),
+                    resource.change
+                ),
+            ));
+        }
+        resource.value = clamped;
+        resource.change = 0;
     }
+    turn.0 += 1;
     state.set(TurnState::WaitingForGod);
 }
 
@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::effect::Condition;
+use super::kingdom::{Kingdom, KingdomID};
+use super::resource::{Resource, ResourceType};
+use super::{kingdom_snapshot, Log, Turn};
+
+const ENDINGS_DATA: &str = include_str!("../../assets/data/endings.ron");
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum EndCondition {
+    EveryKingdom(Condition),
+    AnyKingdom(Condition),
+    // At least this many turns have been played.
+    Survive(usize),
+    // Conquest has left fewer than this many kingdoms.
+    KingdomsBelow(usize),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EndRule {
+    pub name: String,
+    pub message: String,
+    pub condition: EndCondition,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EndRules {
+    pub victory: Vec<EndRule>,
+    pub defeat: Vec<EndRule>,
+}
+
+impl Default for EndRules {
+    fn default() -> Self {
+        ron::from_str(ENDINGS_DATA).expect("Could not parse endings.ron")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameOutcome {
+    pub victory: bool,
+    pub name: String,
+    pub message: String,
+    pub turn: usize,
+}
+
+// Set once the game has been won or lost. The god can't act until a new game is loaded.
+#[derive(Debug, Clone, Default)]
+pub struct GameOver(pub Option<GameOutcome>);
+
+pub struct GameOverEvent(pub GameOutcome);
+
+// Runs on entering `TurnState::WaitingForGod`, once the turn's changes have been applied.
+pub fn check_game_over(
+    rules: Res<EndRules>,
+    turn: Res<Turn>,
+    kingdom_query: Query<&KingdomID, With<Kingdom>>,
+    resource_query: Query<(&Resource, &ResourceType, &KingdomID)>,
+    mut game_over: ResMut<GameOver>,
+    mut ev_game_over: EventWriter<GameOverEvent>,
+    mut log: ResMut<Log>,
+) {
+    if game_over.0.is_some() {
+        return;
+    }
+    let kingdoms: Vec<_> = kingdom_query
+        .iter()
+        .map(|kingdom| kingdom_snapshot(*kingdom, resource_query.iter()))
+        .collect();
+    let holds = |condition: &EndCondition| match condition {
+        EndCondition::EveryKingdom(condition) => {
+            !kingdoms.is_empty() && kingdoms.iter().all(|resources| condition.holds(resources))
+        }
+        EndCondition::AnyKingdom(condition) => {
+            kingdoms.iter().any(|resources| condition.holds(resources))
+        }
+        EndCondition::Survive(turns) => turn.0 >= *turns,
+        EndCondition::KingdomsBelow(count) => kingdoms.len() < *count,
+    };
+
+    let defeat = rules.defeat.iter().find(|rule| holds(&rule.condition));
+    let victory = rules.victory.iter().find(|rule| holds(&rule.condition));
+    let (won, rule) = match (defeat, victory) {
+        (Some(rule), _) => (false, rule),
+        (None, Some(rule)) => (true, rule),
+        (None, None) => return,
+    };
+    let outcome = GameOutcome {
+        victory: won,
+        name: rule.name.clone(),
+        message: rule.message.clone(),
+        turn: turn.0,
+    };
+    log.0.push((
+        format!(
+            "{}: {}",
+            if won { "Victory" } else { "Defeat" },
+            outcome.name
+        ),
+        outcome.message.clone(),
+    ));
+    game_over.0 = Some(outcome.clone());
+    ev_game_over.send(GameOverEvent(outcome));
+}
@@ -0,0 +1,192 @@
+use std::collections::VecDeque;
+use std::fs;
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::god::PurchaseUpgradeEvent;
+use super::miracle::MiracleEvent;
+use super::save::{write_ron, GameSnapshot, LoadGameEvent, SnapshotParams};
+use super::task::TaskProgressEvent;
+use super::{ActionSource, GodActionEvent, ResourceAlterationEvent, Turn, TurnState};
+
+pub const REPLAY_VERSION: u32 = 1;
+pub const REPLAY_PATH: &str = "saves/replay.ron";
+
+// Everything the god did on one turn. A turn with no actions was passed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TurnRecord {
+    pub turn: usize,
+    pub actions: Vec<ResourceAlterationEvent>,
+    #[serde(default)]
+    pub task_progress: Vec<TaskProgressEvent>,
+    #[serde(default)]
+    pub purchases: Vec<PurchaseUpgradeEvent>,
+    #[serde(default)]
+    pub miracles: Vec<MiracleEvent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub start: GameSnapshot,
+    pub turns: Vec<TurnRecord>,
+}
+
+impl Replay {
+    pub fn read(path: &str) -> Result<Replay, String> {
+        let data = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let replay: Replay = ron::from_str(&data).map_err(|e| e.to_string())?;
+        if replay.version > REPLAY_VERSION {
+            return Err(format!(
+                "Replay version {} is newer than this game ({})",
+                replay.version, REPLAY_VERSION
+            ));
+        }
+        return Ok(replay);
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), String> {
+        write_ron(path, self)
+    }
+}
+
+// Records the session as it is played. `path` is where it is written after every turn;
+// `None` (the default) keeps it in memory only.
+#[derive(Default)]
+pub struct ReplayRecorder {
+    pub path: Option<String>,
+    pub replay: Option<Replay>,
+}
+
+impl ReplayRecorder {
+    pub fn start(&mut self, snapshot: GameSnapshot) {
+        self.replay = Some(Replay {
+            version: REPLAY_VERSION,
+            start: snapshot,
+            turns: Vec::new(),
+        });
+    }
+
+    // Forgets everything from `turn` onwards, e.g. after rewinding to it.
+    pub fn truncate(&mut self, turn: usize) {
+        if let Some(replay) = &mut self.replay {
+            replay.turns.retain(|record| record.turn < turn);
+        }
+    }
+}
+
+// Feeds a replay back into the game one turn per frame.
+#[derive(Default)]
+pub struct ReplayPlayer {
+    start: Option<GameSnapshot>,
+    turns: VecDeque<TurnRecord>,
+}
+
+impl ReplayPlayer {
+    pub fn new(replay: Replay) -> Self {
+        ReplayPlayer {
+            start: Some(replay.start),
+            turns: replay.turns.into_iter().collect(),
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.start.is_none() && self.turns.is_empty()
+    }
+}
+
+pub fn begin_recording(mut recorder: ResMut<ReplayRecorder>, snapshot: SnapshotParams) {
+    if recorder.replay.is_none() {
+        recorder.start(snapshot.capture());
+    }
+}
+
+// Runs as the first step of `TurnState::CountingChanges`, before any other part of the game
+// adds its own changes to the tally.
+pub fn record_actions(
+    mut recorder: ResMut<ReplayRecorder>,
+    mut ev_resource_changes: EventReader<ResourceAlterationEvent>,
+    mut ev_progress: EventReader<TaskProgressEvent>,
+    mut ev_purchase: EventReader<PurchaseUpgradeEvent>,
+    mut ev_miracle: EventReader<MiracleEvent>,
+    turn: Res<Turn>,
+) {
+    let actions: Vec<ResourceAlterationEvent> = ev_resource_changes
+        .iter()
+        .filter(|event| event.source == ActionSource::Player)
+        .cloned()
+        .collect();
+    if let Some(replay) = &mut recorder.replay {
+        replay.turns.push(TurnRecord {
+            turn: turn.0,
+            actions,
+            task_progress: ev_progress.iter().cloned().collect(),
+            purchases: ev_purchase.iter().cloned().collect(),
+            miracles: ev_miracle.iter().cloned().collect(),
+        });
+    }
+}
+
+pub fn write_replay(recorder: Res<ReplayRecorder>) {
+    if let (Some(path), Some(replay)) = (&recorder.path, &recorder.replay) {
+        if let Err(error) = replay.write(path) {
+            error!("Could not write the replay: {}", error);
+        }
+    }
+}
+
+// Everything a recorded turn sends, in the order the god would have.
+#[derive(SystemParam)]
+pub struct RecordedActions<'w, 's> {
+    ev_resource_changes: EventWriter<'w, 's, ResourceAlterationEvent>,
+    ev_progress: EventWriter<'w, 's, TaskProgressEvent>,
+    ev_purchase: EventWriter<'w, 's, PurchaseUpgradeEvent>,
+    ev_miracle: EventWriter<'w, 's, MiracleEvent>,
+    ev_godaction: EventWriter<'w, 's, GodActionEvent>,
+}
+
+impl<'w, 's> RecordedActions<'w, 's> {
+    fn send(&mut self, record: TurnRecord) {
+        for action in record.actions.into_iter() {
+            self.ev_resource_changes.send(action);
+        }
+        for progress in record.task_progress.into_iter() {
+            self.ev_progress.send(progress);
+        }
+        for purchase in record.purchases.into_iter() {
+            self.ev_purchase.send(purchase);
+        }
+        for miracle in record.miracles.into_iter() {
+            self.ev_miracle.send(miracle);
+        }
+        self.ev_godaction.send(GodActionEvent);
+    }
+}
+
+pub fn play_replay(
+    mut player: ResMut<ReplayPlayer>,
+    state: Res<State<TurnState>>,
+    turn: Res<Turn>,
+    mut ev_load: EventWriter<LoadGameEvent>,
+    mut actions: RecordedActions,
+) {
+    if let Some(start) = player.start.take() {
+        ev_load.send(LoadGameEvent(start));
+        return;
+    }
+    if *state.current() != TurnState::WaitingForGod {
+        return;
+    }
+    while player
+        .turns
+        .front()
+        .map_or(false, |record| record.turn < turn.0)
+    {
+        player.turns.pop_front();
+    }
+    if let Some(record) = player.turns.pop_front() {
+        actions.send(record);
+    }
+}
@@ -1,10 +1,13 @@
 use std::sync::Arc;
 
 use bevy::{prelude::*, utils::hashbrown::HashMap};
+use serde::{Deserialize, Serialize};
 use strum::IntoEnumIterator;
 use strum_macros::{AsRefStr, EnumCount, EnumIter};
 
-#[derive(Debug, EnumIter, EnumCount, AsRefStr, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(
+    Debug, EnumIter, EnumCount, AsRefStr, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
 pub enum ResourceTypes {
     Food,
     Industry,
@@ -23,30 +26,145 @@ pub const STARTING_RESOURCES: [ResourceTypes; 6] = [
     ResourceTypes::Happiness,
 ];
 
+impl ResourceTypes {
+    pub fn default_bounds(&self) -> (isize, isize) {
+        match self {
+            ResourceTypes::Food => (0, 999),
+            ResourceTypes::Industry => (0, 999),
+            ResourceTypes::Faith => (0, 999),
+            ResourceTypes::Populace => (0, 999),
+            ResourceTypes::Military => (0, 999),
+            ResourceTypes::Happiness => (0, 100),
+        }
+    }
+}
+
 #[derive(Debug, Component, Clone)]
 pub struct Resource {
-    pub value: usize,
-    pub change: usize,
+    pub value: isize,
+    pub change: isize,
+}
+
+// Inclusive (min, max) range each resource is clamped to when changes are applied.
+pub struct ResourceBounds(pub HashMap<ResourceTypes, (isize, isize)>);
+
+impl Default for ResourceBounds {
+    fn default() -> Self {
+        let mut bounds = HashMap::new();
+        for resource_type in ResourceTypes::iter() {
+            bounds.insert(resource_type, resource_type.default_bounds());
+        }
+        return ResourceBounds(bounds);
+    }
+}
+
+impl ResourceBounds {
+    pub fn get(&self, resource: ResourceTypes) -> (isize, isize) {
+        match self.0.get(&resource) {
+            Some(bounds) => *bounds,
+            None => resource.default_bounds(),
+        }
+    }
+
+    pub fn set(&mut self, resource: ResourceTypes, min: isize, max: isize) {
+        self.0.insert(resource, (min.min(max), max.max(min)));
+    }
 }
 
 #[derive(Component, PartialEq)]
 pub struct ResourceType(pub ResourceTypes);
 
-#[derive(Component)]
-pub struct ResourceModification(Box<dyn Fn(KingdomResources) -> KingdomResources + Send + Sync>);
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ModifierLayer {
+    Add(isize),
+    Multiply(f32),
+}
+
+// Alters a kingdom's change in one resource every turn until it runs out. `turns: None` lasts
+// forever.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResourceModification {
+    pub source: String,
+    pub resource: ResourceTypes,
+    pub layer: ModifierLayer,
+    pub turns: Option<usize>,
+}
 
 impl ResourceModification {
-    pub fn IncMod(resource: ResourceTypes, inc: usize) -> Self {
-        return ResourceModification(Box::new(move |kingdom_res: KingdomResources| {
-            let mut new_resources: KingdomResources = KingdomResources::new();
-            new_resources.0.insert(resource, inc);
-            return new_resources;
-        }));
+    // A one-off bonus paid out on the turn it is added.
+    pub fn IncMod(resource: ResourceTypes, inc: isize) -> Self {
+        return ResourceModification {
+            source: String::new(),
+            resource,
+            layer: ModifierLayer::Add(inc),
+            turns: Some(1),
+        };
+    }
+
+    pub fn from_source(self, source: &str) -> Self {
+        ResourceModification {
+            source: source.to_string(),
+            ..self
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let layer = match self.layer {
+            ModifierLayer::Add(amount) => format!("{:+} {}", amount, self.resource.as_ref()),
+            ModifierLayer::Multiply(factor) => format!("{} x{}", self.resource.as_ref(), factor),
+        };
+        match self.turns {
+            Some(1) => format!("{} this turn", layer),
+            Some(turns) => format!("{} for {} turns", layer, turns),
+            None => layer,
+        }
+    }
+}
+
+// A kingdom's active modifiers. Each turn, a resource's change has every additive layer added to
+// it, then, if it is a gain, is scaled by every multiplicative layer.
+#[derive(Debug, Component, Clone, Default)]
+pub struct Modifiers(pub Vec<ResourceModification>);
+
+impl Modifiers {
+    pub fn modify(&self, resource: ResourceTypes, change: isize) -> isize {
+        let layers = self
+            .0
+            .iter()
+            .filter(|modifier| modifier.resource == resource);
+        let mut change = change
+            + layers
+                .clone()
+                .filter_map(|modifier| match modifier.layer {
+                    ModifierLayer::Add(amount) => Some(amount),
+                    _ => None,
+                })
+                .sum::<isize>();
+        if change > 0 {
+            let factor: f32 = layers
+                .filter_map(|modifier| match modifier.layer {
+                    ModifierLayer::Multiply(factor) => Some(factor),
+                    _ => None,
+                })
+                .product();
+            change = (change as f32 * factor).round() as isize;
+        }
+        return change;
+    }
+
+    // Counts down every timed modifier and drops the ones that have run out.
+    pub fn tick(&mut self) {
+        for modifier in self.0.iter_mut() {
+            if let Some(turns) = &mut modifier.turns {
+                *turns = turns.saturating_sub(1);
+            }
+        }
+        self.0.retain(|modifier| modifier.turns != Some(0));
     }
 }
 
 #[derive(Clone)]
-pub struct KingdomResources(HashMap<ResourceTypes, usize>);
+pub struct KingdomResources(HashMap<ResourceTypes, isize>);
 
 impl KingdomResources {
     pub fn new() -> KingdomResources {
@@ -57,11 +175,15 @@ impl KingdomResources {
         return KingdomResources(resources);
     }
 
-    pub fn set(&mut self, resource: ResourceTypes, value: usize) {
+    pub fn get(&self, resource: ResourceTypes) -> isize {
+        *self.0.get(&resource).unwrap_or(&0)
+    }
+
+    pub fn set(&mut self, resource: ResourceTypes, value: isize) {
         (*self).0.insert(resource, value);
     }
 
-    pub fn add(&mut self, resource: ResourceTypes, value: usize) {
+    pub fn add(&mut self, resource: ResourceTypes, value: isize) {
         *((*self).0.entry(resource).or_insert(0)) += value;
     }
 }
@@ -0,0 +1,243 @@
+use bevy::ecs::event::{Events, ManualEventReader};
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use super::effect::Effect;
+use super::god::TITHE_DIVISOR;
+use super::kingdom::{Kingdom, KingdomID};
+use super::resource::{Resource, ResourceType, ResourceTypes};
+use super::rng::{GameRng, RngStream};
+use super::{kingdom_snapshot, ActionSource, Log, ResourceAlterationEvent};
+
+const RIVALS_DATA: &str = include_str!("../../assets/data/rivals.ron");
+
+// Devotion every rival loses in a kingdom each time the player blesses it.
+pub const PLAYER_SWAY: isize = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RivalAction {
+    pub name: String,
+    // `{god}` and `{kingdom}` are replaced with their names.
+    pub message: String,
+    pub cost: isize,
+    pub devotion: isize,
+    pub effects: Vec<Effect>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RivalGodDef {
+    pub name: String,
+    // Faith the rival starts a game with.
+    pub faith: isize,
+    // Faith the rival gains every turn, on top of its tithe.
+    pub income: isize,
+    pub actions: Vec<RivalAction>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Pantheon(pub Vec<RivalGodDef>);
+
+impl Default for Pantheon {
+    fn default() -> Self {
+        Pantheon(ron::from_str(RIVALS_DATA).expect("Could not parse rivals.ron"))
+    }
+}
+
+// A rival's standing in the game. Rivals are kept in pantheon order, which is what
+// `ActionSource::Rival` counts in.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RivalGod {
+    pub name: String,
+    pub faith: isize,
+}
+
+#[derive(Debug, Clone)]
+pub struct RivalGods(pub Vec<RivalGod>);
+
+impl Default for RivalGods {
+    fn default() -> Self {
+        RivalGods::from_pantheon(&Pantheon::default())
+    }
+}
+
+impl RivalGods {
+    pub fn from_pantheon(pantheon: &Pantheon) -> Self {
+        RivalGods(
+            pantheon
+                .0
+                .iter()
+                .map(|rival| RivalGod {
+                    name: rival.name.clone(),
+                    faith: rival.faith,
+                })
+                .collect(),
+        )
+    }
+}
+
+// How much of a kingdom's Faith each rival god has won over. The rest belongs to the player.
+#[derive(Debug, Component, Clone, Default)]
+pub struct Devotion(pub Vec<(String, isize)>);
+
+impl Devotion {
+    pub fn of(&self, god: &str) -> isize {
+        self.0
+            .iter()
+            .find(|(name, _)| name == god)
+            .map_or(0, |(_, amount)| *amount)
+    }
+
+    pub fn total(&self) -> isize {
+        self.0.iter().map(|(_, amount)| *amount).sum()
+    }
+
+    pub fn shift(&mut self, god: &str, amount: isize) {
+        match self.0.iter_mut().find(|(name, _)| name == god) {
+            Some((_, devotion)) => *devotion = (*devotion + amount).max(0),
+            None => self.0.push((god.to_string(), amount.max(0))),
+        }
+        self.0.retain(|(_, amount)| *amount > 0);
+    }
+
+    // Shrinks every rival's share alike until together they hold no more than `faith`.
+    pub fn settle(&mut self, faith: isize) {
+        let total = self.total();
+        if total <= faith {
+            return;
+        }
+        let faith = faith.max(0);
+        for (_, amount) in self.0.iter_mut() {
+            *amount = *amount * faith / total;
+        }
+        self.0.retain(|(_, amount)| *amount > 0);
+    }
+}
+
+// Runs during `TurnState::CountingChanges`, after random events. The player's blessings this turn
+// win back devotion before the rivals make their moves, which go into the same tally.
+pub fn rival_gods_act(
+    pantheon: Res<Pantheon>,
+    mut rivals: ResMut<RivalGods>,
+    mut rng: ResMut<GameRng>,
+    mut kingdom_query: Query<(&KingdomID, &Name, &mut Devotion), With<Kingdom>>,
+    resource_query: Query<(&Resource, &ResourceType, &KingdomID)>,
+    // Read and written here, so it can't be an `EventReader` and `EventWriter` pair.
+    mut ev_resource_changes: ResMut<Events<ResourceAlterationEvent>>,
+    mut resource_change_reader: Local<ManualEventReader<ResourceAlterationEvent>>,
+) {
+    let blessed: Vec<Vec<KingdomID>> = resource_change_reader
+        .iter(&ev_resource_changes)
+        .filter(|event| matches!(event.source, ActionSource::Player | ActionSource::Miracle))
+        .map(|event| {
+            let mut kingdoms: Vec<KingdomID> =
+                event.changes.iter().map(|(kingdom, _)| *kingdom).collect();
+            kingdoms.sort_by_key(|KingdomID(id)| *id);
+            kingdoms.dedup();
+            kingdoms
+        })
+        .collect();
+    for (kingdom, _, mut devotion) in kingdom_query.iter_mut() {
+        let blessings = blessed
+            .iter()
+            .filter(|kingdoms| kingdoms.contains(kingdom))
+            .count() as isize;
+        if blessings > 0 {
+            for (_, amount) in devotion.0.iter_mut() {
+                *amount = (*amount - blessings * PLAYER_SWAY).max(0);
+            }
+            devotion.0.retain(|(_, amount)| *amount > 0);
+        }
+    }
+
+    // Kingdoms are visited in ID order so the same seed always makes the same choices.
+    let mut kingdoms: Vec<(KingdomID, String, isize)> = kingdom_query
+        .iter()
+        .map(|(kingdom, name, devotion)| {
+            let faith = kingdom_snapshot(*kingdom, resource_query.iter()).get(ResourceTypes::Faith);
+            (*kingdom, name.to_string(), faith - devotion.total())
+        })
+        .collect();
+    kingdoms.sort_by_key(|(KingdomID(id), _, _)| *id);
+
+    let rng = rng.stream(RngStream::Rivals);
+    for (index, (rival, standing)) in pantheon.0.iter().zip(rivals.0.iter_mut()).enumerate() {
+        // Half the time a rival goes after the kingdom with the most Faith still the player's.
+        let target = if rng.gen_bool(0.5) {
+            kingdoms
+                .iter_mut()
+                .max_by_key(|(_, _, unclaimed)| *unclaimed)
+        } else {
+            kingdoms.choose_mut(rng)
+        };
+        let (kingdom, name, unclaimed) = match target {
+            Some(target) => target,
+            None => return,
+        };
+        let affordable: Vec<&RivalAction> = rival
+            .actions
+            .iter()
+            .filter(|action| action.cost <= standing.faith)
+            .collect();
+        let action = match affordable.choose(rng) {
+            Some(action) => *action,
+            None => continue,
+        };
+
+        standing.faith -= action.cost;
+        let won = action.devotion.min(*unclaimed).max(0);
+        *unclaimed -= won;
+        if let Some((_, _, mut devotion)) =
+            kingdom_query.iter_mut().find(|(id, _, _)| **id == *kingdom)
+        {
+            devotion.shift(&rival.name, won);
+        }
+        ev_resource_changes.send(ResourceAlterationEvent {
+            source: ActionSource::Rival(index),
+            message: action
+                .message
+                .replace("{god}", &rival.name)
+                .replace("{kingdom}", name),
+            changes: action
+                .effects
+                .iter()
+                .map(|effect| (*kingdom, effect.clone()))
+                .collect(),
+            cost: action.cost,
+        });
+    }
+}
+
+// Runs in `TurnState::ApplyingChanges` once changes are applied and before the player's tithe.
+// Devotion can't outlast the Faith it was won from, and each rival is paid from what it holds.
+pub fn collect_rival_tithe(
+    pantheon: Res<Pantheon>,
+    mut rivals: ResMut<RivalGods>,
+    mut kingdom_query: Query<(&KingdomID, &mut Devotion), With<Kingdom>>,
+    resource_query: Query<(&Resource, &ResourceType, &KingdomID)>,
+    mut log: ResMut<Log>,
+) {
+    for (kingdom, mut devotion) in kingdom_query.iter_mut() {
+        let faith = resource_query
+            .iter()
+            .find(|(_, ResourceType(resource_type), resource_kingdom)| {
+                *resource_type == ResourceTypes::Faith && *resource_kingdom == kingdom
+            })
+            .map_or(0, |(resource, _, _)| resource.value);
+        devotion.settle(faith);
+    }
+    for (rival, standing) in pantheon.0.iter().zip(rivals.0.iter_mut()) {
+        let tithe: isize = kingdom_query
+            .iter()
+            .map(|(_, devotion)| devotion.of(&rival.name) / TITHE_DIVISOR)
+            .sum();
+        standing.faith += rival.income + tithe;
+        if tithe > 0 {
+            log.0.push((
+                format!("{} grows fat on stolen worship.", rival.name),
+                format!("+{} faith", tithe),
+            ));
+        }
+    }
+}
@@ -0,0 +1,78 @@
+use bevy::utils::hashbrown::HashMap;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use strum_macros::AsRefStr;
+
+// Independent sources of randomness. Drawing from one never shifts the others, so adding
+// flavor text can't change which events roll.
+#[derive(Debug, AsRefStr, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum RngStream {
+    Events,
+    AI,
+    Flavor,
+    Battles,
+    Generation,
+    Rivals,
+    Unrest,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RngState {
+    pub seed: u64,
+    pub positions: Vec<(RngStream, u64)>,
+}
+
+pub struct GameRng {
+    seed: u64,
+    streams: HashMap<RngStream, ChaCha8Rng>,
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        GameRng::new(0)
+    }
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        GameRng {
+            seed,
+            streams: HashMap::new(),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn stream(&mut self, stream: RngStream) -> &mut ChaCha8Rng {
+        let seed = self.seed;
+        self.streams.entry(stream).or_insert_with(|| {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            rng.set_stream(stream as u64);
+            rng
+        })
+    }
+
+    pub fn state(&self) -> RngState {
+        let mut positions: Vec<(RngStream, u64)> = self
+            .streams
+            .iter()
+            .map(|(stream, rng)| (*stream, rng.get_word_pos() as u64))
+            .collect();
+        positions.sort_by_key(|(stream, _)| *stream as u64);
+        return RngState {
+            seed: self.seed,
+            positions,
+        };
+    }
+
+    pub fn restore(state: &RngState) -> Self {
+        let mut rng = GameRng::new(state.seed);
+        for (stream, position) in state.positions.iter() {
+            rng.stream(*stream).set_word_pos(*position as u128);
+        }
+        return rng;
+    }
+}
@@ -0,0 +1,423 @@
+use std::fs;
+use std::path::Path;
+
+use bevy::ecs::system::{SystemParam, SystemState};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::ai::{KingdomAi, Personality};
+use super::diplomacy::{Relation, Relations};
+use super::generation::KingdomGenerator;
+use super::god::GodStats;
+use super::history::TurnHistory;
+use super::kingdom::{
+    spawn_kingdom, Boon, Boons, Culture, Kingdom, KingdomID, Ruler, UnlockedBlessings,
+    WorldSettings,
+};
+use super::outcome::GameOver;
+use super::replay::ReplayRecorder;
+use super::resource::{
+    Modifiers, Resource, ResourceModification, ResourceType, ResourceTypes, STARTING_RESOURCES,
+};
+use super::rivals::{Devotion, RivalGod, RivalGods};
+use super::rng::{GameRng, RngState, RngStream};
+use super::task::{spawn_starting_tasks, spawn_task, task_by_name, Task, STARTING_TASKS};
+use super::traits::Traits;
+use super::unrest::Unrest;
+use super::{Log, Turn, TurnState};
+
+// Bump this whenever `GameSnapshot` changes shape, and teach `read_save` about the old one.
+pub const SAVE_VERSION: u32 = 14;
+pub const SAVE_PATH: &str = "saves/savegame.ron";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KingdomSave {
+    pub id: KingdomID,
+    pub name: String,
+    pub resources: Vec<(ResourceTypes, isize)>,
+    #[serde(default)]
+    pub boons: Vec<Boon>,
+    #[serde(default)]
+    pub unlocked_blessings: Vec<String>,
+    #[serde(default)]
+    pub modifiers: Vec<ResourceModification>,
+    #[serde(default)]
+    pub personality: String,
+    #[serde(default)]
+    pub ruler: String,
+    #[serde(default)]
+    pub culture: String,
+    #[serde(default)]
+    pub traits: Vec<String>,
+    #[serde(default)]
+    pub devotion: Vec<(String, isize)>,
+    #[serde(default)]
+    pub unrest: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskSave {
+    pub kingdom: KingdomID,
+    pub name: String,
+    pub progress: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameSnapshot {
+    pub version: u32,
+    #[serde(default)]
+    pub turn: usize,
+    pub turn_state: TurnState,
+    pub kingdoms: Vec<KingdomSave>,
+    pub log: Vec<(String, String)>,
+    #[serde(default)]
+    pub tasks: Vec<TaskSave>,
+    #[serde(default)]
+    pub rng: Option<RngState>,
+    #[serde(default)]
+    pub god: GodStats,
+    #[serde(default)]
+    pub relations: Vec<Relation>,
+    // Saves from before rival gods start them afresh.
+    #[serde(default)]
+    pub rivals: Vec<RivalGod>,
+}
+
+// Where the game autosaves after every turn. `None` (the default) never touches the disk.
+#[derive(Default)]
+pub struct Autosave(pub Option<String>);
+
+pub struct LoadGameEvent(pub GameSnapshot);
+
+pub struct GameLoadedEvent;
+
+// Throws away the current game for a fresh world rolled from `seed`.
+pub struct NewGameEvent {
+    pub seed: u64,
+}
+
+// The components a kingdom is saved with, besides its resources.
+type SavedComponents = (
+    &'static KingdomID,
+    &'static Name,
+    &'static Boons,
+    &'static UnlockedBlessings,
+    &'static Modifiers,
+    &'static Personality,
+    &'static Ruler,
+    &'static Culture,
+    &'static Traits,
+    &'static Devotion,
+    &'static Unrest,
+);
+
+// Everything a `GameSnapshot` is built from, so any system can take a snapshot.
+#[derive(SystemParam)]
+pub struct SnapshotParams<'w, 's> {
+    kingdom_query: Query<'w, 's, SavedComponents, With<Kingdom>>,
+    resource_query: Query<'w, 's, (&'static Resource, &'static ResourceType, &'static KingdomID)>,
+    task_query: Query<'w, 's, (&'static Task, &'static KingdomID)>,
+    log: Res<'w, Log>,
+    turn: Res<'w, Turn>,
+    state: Res<'w, State<TurnState>>,
+    rng: Res<'w, GameRng>,
+    god: Res<'w, GodStats>,
+    relations: Res<'w, Relations>,
+    rivals: Res<'w, RivalGods>,
+}
+
+impl<'w, 's> SnapshotParams<'w, 's> {
+    pub fn capture(&self) -> GameSnapshot {
+        let mut kingdoms: Vec<KingdomSave> = self
+            .kingdom_query
+            .iter()
+            .map(
+                |(
+                    id,
+                    name,
+                    boons,
+                    unlocked,
+                    modifiers,
+                    personality,
+                    ruler,
+                    culture,
+                    traits,
+                    devotion,
+                    unrest,
+                )| {
+                    KingdomSave {
+                        id: *id,
+                        name: name.to_string(),
+                        resources: STARTING_RESOURCES
+                            .iter()
+                            .filter_map(|resource_type| {
+                                self.resource_query
+                                    .iter()
+                                    .find(|(_, ResourceType(r), kingdom)| {
+                                        r == resource_type && *kingdom == id
+                                    })
+                                    .map(|(resource, _, _)| (*resource_type, resource.value))
+                            })
+                            .collect(),
+                        boons: boons.0.clone(),
+                        unlocked_blessings: unlocked.0.clone(),
+                        modifiers: modifiers.0.clone(),
+                        personality: personality.0.clone(),
+                        ruler: ruler.0.clone(),
+                        culture: culture.0.clone(),
+                        traits: traits.0.clone(),
+                        devotion: devotion.0.clone(),
+                        unrest: unrest.0,
+                    }
+                },
+            )
+            .collect();
+        kingdoms.sort_by_key(|kingdom| kingdom.id.0);
+
+        return GameSnapshot {
+            version: SAVE_VERSION,
+            turn: self.turn.0,
+            turn_state: self.state.current().clone(),
+            kingdoms,
+            log: self.log.0.clone(),
+            tasks: self
+                .task_query
+                .iter()
+                .map(|(task, kingdom)| TaskSave {
+                    kingdom: *kingdom,
+                    name: task.name.to_string(),
+                    progress: task.progress,
+                })
+                .collect(),
+            rng: Some(self.rng.state()),
+            god: self.god.clone(),
+            relations: self.relations.0.clone(),
+            rivals: self.rivals.0.clone(),
+        };
+    }
+}
+
+pub fn capture_snapshot(world: &mut World) -> GameSnapshot {
+    let mut system_state: SystemState<SnapshotParams> = SystemState::new(world);
+    return system_state.get_mut(world).capture();
+}
+
+pub fn write_save(path: &str, snapshot: &GameSnapshot) -> Result<(), String> {
+    write_ron(path, snapshot)
+}
+
+pub fn write_ron<T: Serialize>(path: &str, value: &T) -> Result<(), String> {
+    if let Some(parent) = Path::new(path).parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+        .map_err(|e| e.to_string())?;
+    return fs::write(path, data).map_err(|e| e.to_string());
+}
+
+pub fn read_save(path: &str) -> Result<GameSnapshot, String> {
+    let data = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let snapshot: GameSnapshot = ron::from_str(&data).map_err(|e| e.to_string())?;
+    if snapshot.version > SAVE_VERSION {
+        return Err(format!(
+            "Save version {} is newer than this game ({})",
+            snapshot.version, SAVE_VERSION
+        ));
+    }
+    return Ok(snapshot);
+}
+
+// The world a new game with `seed` starts in, the same one `setup_kingdoms` spawns.
+pub fn new_game_snapshot(
+    seed: u64,
+    settings: &WorldSettings,
+    ai: &KingdomAi,
+    generator: &KingdomGenerator,
+) -> GameSnapshot {
+    let mut rng = GameRng::new(seed);
+    let generated = generator.generate_world(
+        settings.kingdom_count,
+        &ai.personalities,
+        rng.stream(RngStream::Generation),
+    );
+    let kingdoms: Vec<KingdomSave> = generated
+        .into_iter()
+        .enumerate()
+        .map(|(i, kingdom)| KingdomSave {
+            id: KingdomID(i + 1),
+            name: kingdom.name,
+            resources: kingdom.resources,
+            boons: Vec::new(),
+            unlocked_blessings: Vec::new(),
+            modifiers: Vec::new(),
+            personality: kingdom.personality,
+            ruler: kingdom.ruler,
+            culture: kingdom.culture,
+            traits: kingdom.traits,
+            devotion: Vec::new(),
+            unrest: 0,
+        })
+        .collect();
+    let tasks: Vec<TaskSave> = kingdoms
+        .iter()
+        .flat_map(|kingdom| {
+            STARTING_TASKS.iter().map(move |name| TaskSave {
+                kingdom: kingdom.id,
+                name: name.to_string(),
+                progress: 0,
+            })
+        })
+        .collect();
+    return GameSnapshot {
+        version: SAVE_VERSION,
+        turn: 0,
+        turn_state: TurnState::WaitingForGod,
+        kingdoms,
+        log: Vec::new(),
+        tasks,
+        rng: Some(rng.state()),
+        god: GodStats::default(),
+        relations: Vec::new(),
+        rivals: RivalGods::default().0,
+    };
+}
+
+// Runs in `CoreStage::PreUpdate` ahead of `restore_snapshot`, which loads the new world.
+pub fn start_new_game(
+    mut ev_new: EventReader<NewGameEvent>,
+    mut ev_load: EventWriter<LoadGameEvent>,
+    settings: Res<WorldSettings>,
+    ai: Res<KingdomAi>,
+    generator: Res<KingdomGenerator>,
+) {
+    if let Some(NewGameEvent { seed }) = ev_new.iter().last() {
+        ev_load.send(LoadGameEvent(new_game_snapshot(
+            *seed, &settings, &ai, &generator,
+        )));
+    }
+}
+
+pub fn save_exists(path: &str) -> bool {
+    Path::new(path).exists()
+}
+
+pub fn autosave(autosave: Res<Autosave>, snapshot: SnapshotParams) {
+    if snapshot.turn.0 == 0 {
+        // Don't clobber the last save with a fresh game before the player has done anything.
+        return;
+    }
+    if let Some(path) = &autosave.0 {
+        if let Err(error) = write_save(path, &snapshot.capture()) {
+            error!("Could not save the game: {}", error);
+        }
+    }
+}
+
+// Everything loading a `GameSnapshot` throws away or overwrites.
+#[derive(SystemParam)]
+pub struct RestoreParams<'w, 's> {
+    kingdom_query: Query<'w, 's, Entity, With<Kingdom>>,
+    task_query: Query<'w, 's, Entity, With<Task>>,
+    log: ResMut<'w, Log>,
+    turn: ResMut<'w, Turn>,
+    history: ResMut<'w, TurnHistory>,
+    recorder: ResMut<'w, ReplayRecorder>,
+    rng: ResMut<'w, GameRng>,
+    god: ResMut<'w, GodStats>,
+    relations: ResMut<'w, Relations>,
+    game_over: ResMut<'w, GameOver>,
+    rivals: ResMut<'w, RivalGods>,
+    state: ResMut<'w, State<TurnState>>,
+}
+
+// Runs in `CoreStage::PreUpdate` so the respawned kingdoms exist before anything in `Update`
+// reacts to `GameLoadedEvent`.
+pub fn restore_snapshot(
+    mut commands: Commands,
+    mut ev_load: EventReader<LoadGameEvent>,
+    mut ev_loaded: EventWriter<GameLoadedEvent>,
+    generator: Res<KingdomGenerator>,
+    mut game: RestoreParams,
+) {
+    if let Some(LoadGameEvent(snapshot)) = ev_load.iter().last() {
+        for kingdom in game.kingdom_query.iter() {
+            commands.entity(kingdom).despawn_recursive();
+        }
+        for kingdom in snapshot.kingdoms.iter() {
+            let entity = spawn_kingdom(
+                &mut commands,
+                kingdom.id,
+                kingdom.name.clone(),
+                &kingdom.resources,
+            );
+            commands
+                .entity(entity)
+                .insert(Boons(kingdom.boons.clone()))
+                .insert(UnlockedBlessings(kingdom.unlocked_blessings.clone()))
+                .insert(Modifiers(kingdom.modifiers.clone()))
+                .insert(Personality(kingdom.personality.clone()))
+                .insert(Ruler(kingdom.ruler.clone()))
+                .insert(Culture(kingdom.culture.clone()));
+            // Traits came from the culture before they were saved separately.
+            let traits = if snapshot.version < 11 {
+                generator
+                    .culture(&kingdom.culture)
+                    .map_or(Vec::new(), |culture| culture.traits.clone())
+            } else {
+                kingdom.traits.clone()
+            };
+            commands
+                .entity(entity)
+                .insert(Traits(traits))
+                .insert(Devotion(kingdom.devotion.clone()))
+                .insert(Unrest(kingdom.unrest));
+        }
+        for task in game.task_query.iter() {
+            commands.entity(task).despawn();
+        }
+        if snapshot.version < 4 {
+            // Tasks didn't exist yet, so give every kingdom the ones a new game starts with.
+            for kingdom in snapshot.kingdoms.iter() {
+                spawn_starting_tasks(&mut commands, kingdom.id);
+            }
+        } else if snapshot.version < 5 {
+            // The temple storyline came later; start it in kingdoms saved before then.
+            for kingdom in snapshot.kingdoms.iter() {
+                if let Some(task) = task_by_name("Build a Temple") {
+                    spawn_task(&mut commands, kingdom.id, task);
+                }
+            }
+        }
+        for saved in snapshot.tasks.iter() {
+            if let Some(mut task) = task_by_name(&saved.name) {
+                task.progress = saved.progress;
+                spawn_task(&mut commands, saved.kingdom, task);
+            }
+        }
+        game.log.0 = snapshot.log.clone();
+        game.turn.0 = snapshot.turn;
+        if let Some(rng_state) = &snapshot.rng {
+            *game.rng = GameRng::restore(rng_state);
+        }
+        *game.god = snapshot.god.clone();
+        game.relations.0 = snapshot.relations.clone();
+        game.game_over.0 = None;
+        *game.rivals = if snapshot.rivals.is_empty() {
+            RivalGods::default()
+        } else {
+            RivalGods(snapshot.rivals.clone())
+        };
+        // Rewinding keeps the history up to the target turn; anything else starts afresh.
+        if game.history.finish_rewind(snapshot.turn) {
+            game.recorder.truncate(snapshot.turn);
+        } else {
+            game.history.clear();
+            game.history.push(snapshot.clone());
+            game.recorder.start(snapshot.clone());
+        }
+        if *game.state.current() != snapshot.turn_state {
+            let _ = game.state.set(snapshot.turn_state.clone());
+        }
+        ev_loaded.send(GameLoadedEvent);
+    }
+}
@@ -0,0 +1,262 @@
+use bevy::ecs::event::Events;
+use bevy::prelude::*;
+
+use super::blessing::Blessings;
+use super::diplomacy::Relations;
+use super::god::{GodStats, PurchaseUpgradeEvent, Upgrade};
+use super::history::{RewindEvent, UndoEvent};
+use super::kingdom::{Kingdom, KingdomID, KingdomPlugin, WorldSettings};
+use super::miracle::MiracleEvent;
+use super::outcome::{GameOutcome, GameOver};
+use super::replay::{Replay, ReplayRecorder};
+use super::resource::{KingdomResources, Resource, ResourceType, ResourceTypes};
+use super::rivals::RivalGods;
+use super::rng::GameRng;
+use super::save::{capture_snapshot, GameSnapshot, LoadGameEvent};
+use super::task::TaskProgressEvent;
+use super::{kingdom_snapshot, GamePlugin, GodActionEvent, Log, ResourceAlterationEvent, Turn};
+
+// Drives the game rules without a window. Each call to `act` or `pass` plays one full turn.
+pub struct Simulation {
+    pub app: App,
+}
+
+impl Simulation {
+    pub fn new() -> Self {
+        Simulation::with_seed(0)
+    }
+
+    pub fn with_seed(seed: u64) -> Self {
+        Simulation::with_settings(seed, WorldSettings::default())
+    }
+
+    pub fn with_settings(seed: u64, settings: WorldSettings) -> Self {
+        let mut app = App::new();
+        app.insert_resource(GameRng::new(seed))
+            .insert_resource(settings)
+            .add_plugins(MinimalPlugins)
+            .add_plugin(KingdomPlugin)
+            .add_plugin(GamePlugin);
+        // Run the startup systems so the kingdoms exist before the first turn.
+        app.update();
+        return Simulation { app };
+    }
+
+    pub fn act(&mut self, action: ResourceAlterationEvent) {
+        self.app
+            .world
+            .get_resource_mut::<Events<ResourceAlterationEvent>>()
+            .unwrap()
+            .send(action);
+        self.pass();
+    }
+
+    pub fn bless(&mut self, kingdom: KingdomID, resource: ResourceTypes) {
+        let blessing = self
+            .app
+            .world
+            .get_resource::<Blessings>()
+            .unwrap()
+            .for_resource(resource)
+            .cloned();
+        let kingdoms: Vec<KingdomID> = self.kingdoms().into_iter().map(|(id, _)| id).collect();
+        let god = self.god().clone();
+        match blessing {
+            Some(blessing) => {
+                let action = god.empower(blessing.alteration(kingdom), &kingdoms);
+                if god.can_afford(action.cost) {
+                    self.act(action)
+                } else {
+                    self.pass()
+                }
+            }
+            None => self.pass(),
+        }
+    }
+
+    pub fn buy(&mut self, upgrade: Upgrade) {
+        self.app
+            .world
+            .get_resource_mut::<Events<PurchaseUpgradeEvent>>()
+            .unwrap()
+            .send(PurchaseUpgradeEvent(upgrade));
+        self.pass();
+    }
+
+    // Miracles that don't target a kingdom or resource ignore `kingdom` or `resource`.
+    pub fn cast(
+        &mut self,
+        miracle: &str,
+        kingdom: Option<KingdomID>,
+        resource: Option<ResourceTypes>,
+    ) {
+        self.app
+            .world
+            .get_resource_mut::<Events<MiracleEvent>>()
+            .unwrap()
+            .send(MiracleEvent {
+                miracle: miracle.to_string(),
+                kingdom,
+                resource,
+            });
+        self.pass();
+    }
+
+    pub fn work_on(&mut self, kingdom: KingdomID, task: &str, amount: usize) {
+        self.app
+            .world
+            .get_resource_mut::<Events<TaskProgressEvent>>()
+            .unwrap()
+            .send(TaskProgressEvent {
+                kingdom,
+                task: task.to_string(),
+                amount,
+            });
+        self.pass();
+    }
+
+    pub fn pass(&mut self) {
+        self.app
+            .world
+            .get_resource_mut::<Events<GodActionEvent>>()
+            .unwrap()
+            .send(GodActionEvent);
+        self.app.update();
+    }
+
+    pub fn kingdoms(&mut self) -> Vec<(KingdomID, String)> {
+        let mut kingdoms: Vec<(KingdomID, String)> = self
+            .app
+            .world
+            .query_filtered::<(&KingdomID, &Name), With<Kingdom>>()
+            .iter(&self.app.world)
+            .map(|(id, name)| (*id, name.to_string()))
+            .collect();
+        kingdoms.sort_by_key(|(KingdomID(id), _)| *id);
+        return kingdoms;
+    }
+
+    pub fn resources(&mut self, kingdom: KingdomID) -> KingdomResources {
+        let mut query = self
+            .app
+            .world
+            .query::<(&Resource, &ResourceType, &KingdomID)>();
+        return kingdom_snapshot(kingdom, query.iter(&self.app.world));
+    }
+
+    pub fn save(&mut self) -> GameSnapshot {
+        return capture_snapshot(&mut self.app.world);
+    }
+
+    pub fn load(&mut self, snapshot: GameSnapshot) {
+        self.app
+            .world
+            .get_resource_mut::<Events<LoadGameEvent>>()
+            .unwrap()
+            .send(LoadGameEvent(snapshot));
+        self.app.update();
+    }
+
+    pub fn undo(&mut self) {
+        self.app
+            .world
+            .get_resource_mut::<Events<UndoEvent>>()
+            .unwrap()
+            .send(UndoEvent);
+        self.app.update();
+    }
+
+    pub fn rewind(&mut self, turn: usize) {
+        self.app
+            .world
+            .get_resource_mut::<Events<RewindEvent>>()
+            .unwrap()
+            .send(RewindEvent(turn));
+        self.app.update();
+    }
+
+    // The session so far, ready to be written out or played back.
+    pub fn replay(&self) -> Option<Replay> {
+        self.app
+            .world
+            .get_resource::<ReplayRecorder>()
+            .unwrap()
+            .replay
+            .clone()
+    }
+
+    pub fn play_replay(&mut self, replay: &Replay) {
+        self.load(replay.start.clone());
+        for record in replay.turns.iter() {
+            let mut events = self
+                .app
+                .world
+                .get_resource_mut::<Events<ResourceAlterationEvent>>()
+                .unwrap();
+            for action in record.actions.iter() {
+                events.send(action.clone());
+            }
+            let mut progress_events = self
+                .app
+                .world
+                .get_resource_mut::<Events<TaskProgressEvent>>()
+                .unwrap();
+            for progress in record.task_progress.iter() {
+                progress_events.send(progress.clone());
+            }
+            let mut purchase_events = self
+                .app
+                .world
+                .get_resource_mut::<Events<PurchaseUpgradeEvent>>()
+                .unwrap();
+            for purchase in record.purchases.iter() {
+                purchase_events.send(purchase.clone());
+            }
+            let mut miracle_events = self
+                .app
+                .world
+                .get_resource_mut::<Events<MiracleEvent>>()
+                .unwrap();
+            for miracle in record.miracles.iter() {
+                miracle_events.send(miracle.clone());
+            }
+            self.pass();
+        }
+    }
+
+    pub fn turn(&self) -> usize {
+        self.app.world.get_resource::<Turn>().unwrap().0
+    }
+
+    pub fn god(&self) -> &GodStats {
+        self.app.world.get_resource::<GodStats>().unwrap()
+    }
+
+    pub fn rivals(&self) -> &RivalGods {
+        self.app.world.get_resource::<RivalGods>().unwrap()
+    }
+
+    pub fn relations(&self) -> &Relations {
+        self.app.world.get_resource::<Relations>().unwrap()
+    }
+
+    // How the game ended, once it has.
+    pub fn outcome(&self) -> Option<&GameOutcome> {
+        self.app
+            .world
+            .get_resource::<GameOver>()
+            .unwrap()
+            .0
+            .as_ref()
+    }
+
+    pub fn log(&self) -> &Log {
+        self.app.world.get_resource::<Log>().unwrap()
+    }
+}
+
+impl Default for Simulation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
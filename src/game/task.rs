@@ -1,5 +1,23 @@
-use super::resource::ResourceModification;
+use super::blessing::Blessings;
+use super::effect::Effect;
+use super::events::KingdomEvents;
+use super::kingdom::{Boon, Boons, Kingdom, KingdomID, UnlockedBlessings};
+use super::resource::{Resource, ResourceModification, ResourceType, ResourceTypes};
+use super::{kingdom_snapshot, ActionSource, Log, ResourceAlterationEvent};
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
+use bevy::utils::HashMap;
+use serde::{Deserialize, Serialize};
+
+// Every turn, each of a kingdom's tasks advances by its Industry divided by this.
+pub const TASK_INDUSTRY_DIVISOR: isize = 25;
+
+pub const STARTING_TASKS: [&str; 4] = [
+    "Dig Irrigation Canals",
+    "Raise a Granary",
+    "Found a Monastery",
+    "Build a Temple",
+];
 
 #[derive(Component)]
 pub struct Task {
@@ -28,12 +46,286 @@ impl Task {
             completion_outcome,
         };
     }
+
+    pub fn is_complete(&self) -> bool {
+        self.progress >= self.max_progress
+    }
+
+    pub fn fraction(&self) -> f32 {
+        if self.max_progress == 0 {
+            return 1.;
+        }
+        (self.progress as f32 / self.max_progress as f32).min(1.)
+    }
 }
 
 pub enum TaskOutcome {
     ResourceOutcome(ResourceModification),
+    // Starts the named task in the same kingdom, so tasks can be chained into storylines.
+    SpawnTask(&'static str),
+    // Lets the god cast the named locked blessing on the kingdom.
+    UnlockBlessing(&'static str),
+    // Fires the named event from events.ron, ignoring its conditions and weight.
+    TriggerEvent(&'static str),
+    // Applied to the kingdom on every turn from now on.
+    PermanentModifier(Effect),
+    All(Vec<TaskOutcome>),
+}
+
+impl TaskOutcome {
+    // Every outcome that isn't an `All`, in order.
+    pub fn flatten(&self) -> Vec<&TaskOutcome> {
+        match self {
+            TaskOutcome::All(outcomes) => outcomes
+                .iter()
+                .flat_map(|outcome| outcome.flatten())
+                .collect(),
+            outcome => vec![outcome],
+        }
+    }
+}
+
+// Tasks can't be serialized (their outcomes are closures), so saves refer to them by name.
+pub fn task_by_name(name: &str) -> Option<Task> {
+    match name {
+        "Dig Irrigation Canals" => Some(Task::new(
+            "Dig Irrigation Canals",
+            "Channel the rivers into the fields.",
+            "Water flows through the new canals.",
+            20,
+            TaskOutcome::All(vec![
+                TaskOutcome::ResourceOutcome(ResourceModification::IncMod(ResourceTypes::Food, 15)),
+                TaskOutcome::UnlockBlessing("Blessed Harvest"),
+            ]),
+        )),
+        "Raise a Granary" => Some(Task::new(
+            "Raise a Granary",
+            "Build stores against the lean years.",
+            "The granary stands ready for the winter.",
+            30,
+            TaskOutcome::ResourceOutcome(ResourceModification::IncMod(
+                ResourceTypes::Happiness,
+                10,
+            )),
+        )),
+        "Found a Monastery" => Some(Task::new(
+            "Found a Monastery",
+            "Give the devout a place to pray.",
+            "Monks chant in the newly founded monastery.",
+            40,
+            TaskOutcome::ResourceOutcome(ResourceModification::IncMod(ResourceTypes::Faith, 20)),
+        )),
+        "Build a Temple" => Some(Task::new(
+            "Build a Temple",
+            "Raise a great temple in your name.",
+            "The temple's doors open to the faithful.",
+            40,
+            TaskOutcome::All(vec![
+                TaskOutcome::ResourceOutcome(ResourceModification::IncMod(
+                    ResourceTypes::Faith,
+                    10,
+                )),
+                TaskOutcome::SpawnTask("Consecrate the Temple"),
+            ]),
+        )),
+        "Consecrate the Temple" => Some(Task::new(
+            "Consecrate the Temple",
+            "Teach the priests the rites of your worship.",
+            "The temple is consecrated, and its priests know your rites.",
+            30,
+            TaskOutcome::All(vec![
+                TaskOutcome::UnlockBlessing("Temple Rites"),
+                TaskOutcome::SpawnTask("Host a Pilgrimage"),
+            ]),
+        )),
+        "Host a Pilgrimage" => Some(Task::new(
+            "Host a Pilgrimage",
+            "Invite the faithful of every land to the temple.",
+            "Pilgrims crowd the roads to the temple.",
+            50,
+            TaskOutcome::All(vec![
+                TaskOutcome::TriggerEvent("Religious Revival"),
+                TaskOutcome::PermanentModifier(Effect::Add(ResourceTypes::Faith, 1)),
+            ]),
+        )),
+        _ => None,
+    }
 }
 
-pub struct GodStats {
-    click_power: usize,
+pub fn spawn_task(commands: &mut Commands, kingdom: KingdomID, task: Task) -> Entity {
+    commands.spawn().insert(task).insert(kingdom).id()
+}
+
+pub fn spawn_starting_tasks(commands: &mut Commands, kingdom: KingdomID) {
+    for name in STARTING_TASKS.iter() {
+        if let Some(task) = task_by_name(name) {
+            spawn_task(commands, kingdom, task);
+        }
+    }
+}
+
+// The god lending a hand to one task. Tasks are named rather than referenced by entity so
+// the event can be recorded and replayed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TaskProgressEvent {
+    pub kingdom: KingdomID,
+    pub task: String,
+    pub amount: usize,
+}
+
+pub struct TaskCompletedEvent {
+    pub kingdom: KingdomID,
+    pub task: &'static str,
+}
+
+// What a finished task's outcomes can name, and where they are sent.
+#[derive(SystemParam)]
+pub struct TaskOutcomes<'w, 's> {
+    blessings: Res<'w, Blessings>,
+    kingdom_events: Res<'w, KingdomEvents>,
+    ev_resource_changes: EventWriter<'w, 's, ResourceAlterationEvent>,
+    ev_completed: EventWriter<'w, 's, TaskCompletedEvent>,
+}
+
+// Runs during `TurnState::CountingChanges`, before the tally, so completed tasks pay out on the
+// same turn they finish.
+pub fn advance_tasks(
+    mut commands: Commands,
+    mut task_query: Query<(Entity, &mut Task, &KingdomID)>,
+    mut kingdom_query: Query<
+        (&KingdomID, &Name, &mut Boons, &mut UnlockedBlessings),
+        With<Kingdom>,
+    >,
+    resource_query: Query<(&Resource, &ResourceType, &KingdomID)>,
+    mut ev_progress: EventReader<TaskProgressEvent>,
+    mut outcomes: TaskOutcomes,
+    mut log: ResMut<Log>,
+) {
+    let names: HashMap<KingdomID, String> = kingdom_query
+        .iter()
+        .map(|(id, name, _, _)| (*id, name.to_string()))
+        .collect();
+    let kingdom_name = |kingdom: &KingdomID| names.get(kingdom).cloned().unwrap_or_default();
+
+    for TaskProgressEvent {
+        kingdom,
+        task: task_name,
+        amount,
+    } in ev_progress.iter()
+    {
+        for (_, mut task, task_kingdom) in task_query.iter_mut() {
+            if task_kingdom == kingdom && task.name == task_name.as_str() {
+                task.progress += *amount;
+                log.0.push((
+                    format!(
+                        "You hasten work on {} in {}.",
+                        task.name,
+                        kingdom_name(kingdom)
+                    ),
+                    format!("+{} progress", amount),
+                ));
+            }
+        }
+    }
+
+    // Sorted so that completions are logged in the same order on every run.
+    let mut tasks: Vec<(Entity, Mut<Task>, &KingdomID)> = task_query.iter_mut().collect();
+    tasks.sort_by_key(|(_, task, KingdomID(id))| (*id, task.name));
+    for (entity, mut task, kingdom) in tasks {
+        let resources = kingdom_snapshot(*kingdom, resource_query.iter());
+        let production = resources.get(ResourceTypes::Industry) / TASK_INDUSTRY_DIVISOR;
+        task.progress += production.max(0) as usize;
+        if !task.is_complete() {
+            continue;
+        }
+
+        let message = format!("{} ({})", task.completion_message, kingdom_name(kingdom));
+        for outcome in task.completion_outcome.flatten() {
+            match outcome {
+                TaskOutcome::ResourceOutcome(modification) => {
+                    outcomes.ev_resource_changes.send(ResourceAlterationEvent {
+                        source: ActionSource::World,
+                        message: message.clone(),
+                        changes: vec![(
+                            *kingdom,
+                            Effect::Modifier(modification.clone().from_source(task.name)),
+                        )],
+                        cost: 0,
+                    });
+                }
+                TaskOutcome::SpawnTask(name) => match task_by_name(name) {
+                    Some(next) => {
+                        spawn_task(&mut commands, *kingdom, next);
+                    }
+                    None => warn!("{} leads to an unknown task: {}", task.name, name),
+                },
+                TaskOutcome::UnlockBlessing(name) => {
+                    if outcomes.blessings.by_name(name).is_none() {
+                        warn!("{} unlocks an unknown blessing: {}", task.name, name);
+                        continue;
+                    }
+                    for (id, _, _, mut unlocked) in kingdom_query.iter_mut() {
+                        if id == kingdom && !unlocked.0.iter().any(|known| known == name) {
+                            unlocked.0.push(name.to_string());
+                            log.0.push((
+                                format!("{} can now be granted {}.", kingdom_name(kingdom), name),
+                                String::new(),
+                            ));
+                        }
+                    }
+                }
+                TaskOutcome::TriggerEvent(name) => match outcomes.kingdom_events.by_name(name) {
+                    Some(event) => outcomes
+                        .ev_resource_changes
+                        .send(event.alteration(*kingdom, &kingdom_name(kingdom))),
+                    None => warn!("{} triggers an unknown event: {}", task.name, name),
+                },
+                TaskOutcome::PermanentModifier(effect) => {
+                    for (id, _, mut boons, _) in kingdom_query.iter_mut() {
+                        if id == kingdom {
+                            boons.0.push(Boon {
+                                source: task.name.to_string(),
+                                effect: effect.clone(),
+                            });
+                        }
+                    }
+                }
+                TaskOutcome::All(_) => {}
+            }
+        }
+        // Resource outcomes are logged by the tally along with their changes.
+        if !task
+            .completion_outcome
+            .flatten()
+            .iter()
+            .any(|outcome| matches!(outcome, TaskOutcome::ResourceOutcome(_)))
+        {
+            log.0.push((message, String::new()));
+        }
+        outcomes.ev_completed.send(TaskCompletedEvent {
+            kingdom: *kingdom,
+            task: task.name,
+        });
+        commands.entity(entity).despawn();
+    }
+}
+
+// Runs during `TurnState::CountingChanges`, after tasks, so a boon granted this turn already
+// counts.
+pub fn apply_boons(
+    kingdom_query: Query<(&KingdomID, &Name, &Boons), With<Kingdom>>,
+    mut ev_resource_changes: EventWriter<ResourceAlterationEvent>,
+) {
+    let mut kingdoms: Vec<(&KingdomID, &Name, &Boons)> = kingdom_query.iter().collect();
+    kingdoms.sort_by_key(|(KingdomID(id), _, _)| *id);
+    for (kingdom, name, boons) in kingdoms {
+        for boon in boons.0.iter() {
+            ev_resource_changes.send(ResourceAlterationEvent {
+                source: ActionSource::World,
+                message: format!("{} ({})", boon.source, name),
+                changes: vec![(*kingdom, boon.effect.clone())],
+                cost: 0,
+            });
+        }
+    }
 }
@@ -0,0 +1,95 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::resource::ResourceTypes;
+use super::ActionSource;
+
+const TRAIT_DATA: &str = include_str!("../../assets/data/traits.ron");
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TraitDef {
+    pub name: String,
+    pub description: String,
+    #[serde(default)]
+    pub blessings: Vec<(ResourceTypes, f32)>,
+    #[serde(default)]
+    pub economy: Vec<(ResourceTypes, f32)>,
+}
+
+impl TraitDef {
+    fn factors(&self, source: ActionSource) -> &[(ResourceTypes, f32)] {
+        match source {
+            ActionSource::Player
+            | ActionSource::Miracle
+            | ActionSource::Wrath
+            | ActionSource::Rival(_) => &self.blessings,
+            ActionSource::Economy => &self.economy,
+            ActionSource::World => &[],
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let factors = |factors: &[(ResourceTypes, f32)]| {
+            factors
+                .iter()
+                .map(|(resource, factor)| format!("x{} {}", factor, resource.as_ref()))
+                .collect::<Vec<String>>()
+                .join(", ")
+        };
+        let mut lines = vec![self.description.clone()];
+        if !self.blessings.is_empty() {
+            lines.push(format!("Blessings: {}", factors(&self.blessings)));
+        }
+        if !self.economy.is_empty() {
+            lines.push(format!("Each turn: {}", factors(&self.economy)));
+        }
+        return lines.join("\n");
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct KingdomTraits(pub Vec<TraitDef>);
+
+impl Default for KingdomTraits {
+    fn default() -> Self {
+        KingdomTraits(ron::from_str(TRAIT_DATA).expect("Could not parse traits.ron"))
+    }
+}
+
+impl KingdomTraits {
+    pub fn get(&self, name: &str) -> Option<&TraitDef> {
+        self.0.iter().find(|definition| definition.name == name)
+    }
+
+    // How much a change to `resource` from `source` is worth to a kingdom with `traits`. Gains are
+    // multiplied by every matching factor and losses divided by it.
+    pub fn scale(
+        &self,
+        traits: &Traits,
+        source: ActionSource,
+        resource: ResourceTypes,
+        change: isize,
+    ) -> isize {
+        let factor: f32 = traits
+            .0
+            .iter()
+            .filter_map(|name| self.get(name))
+            .flat_map(|definition| definition.factors(source).iter())
+            .filter(|(r, _)| *r == resource)
+            .map(|(_, factor)| *factor)
+            .product();
+        if factor == 1. || factor <= 0. {
+            return change;
+        }
+        let scaled = if change > 0 {
+            change as f32 * factor
+        } else {
+            change as f32 / factor
+        };
+        return scaled.round() as isize;
+    }
+}
+
+// Names of traits from traits.ron. Unknown names do nothing.
+#[derive(Debug, Component, Clone, Default)]
+pub struct Traits(pub Vec<String>);
@@ -0,0 +1,234 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use rand::Rng;
+
+use super::ai::{KingdomAi, Personality};
+use super::diplomacy::{Relation, Relations};
+use super::generation::KingdomGenerator;
+use super::kingdom::{spawn_kingdom, Boons, Culture, Kingdom, KingdomID, Ruler, UnlockedBlessings};
+use super::resource::{
+    KingdomResources, Modifiers, Resource, ResourceType, ResourceTypes, STARTING_RESOURCES,
+};
+use super::rivals::Devotion;
+use super::rng::{GameRng, RngStream};
+use super::task::{spawn_starting_tasks, Task};
+use super::traits::Traits;
+use super::{kingdom_snapshot, Log};
+
+// A kingdom whose Happiness is below this at the end of a turn grows restless.
+pub const UNREST_HAPPINESS: isize = 20;
+// Turns of unrest before rebellion becomes possible.
+pub const UNREST_TURNS: usize = 3;
+// Chance each turn, once unrest has boiled over, that the kingdom rebels.
+pub const REBELLION_CHANCE: f64 = 0.35;
+// Percent of a kingdom's resources the rebels carry off. Each side keeps its own Happiness.
+pub const REBEL_SHARE: isize = 40;
+// Happiness the rebels gain from throwing off their rulers.
+pub const REBEL_ZEAL: isize = 15;
+// Populace each side has to keep for the kingdom to split. Smaller halves would starve out within
+// a few turns and end the game in a `Collapse`.
+pub const REBEL_MIN_POPULACE: isize = 25;
+// How the two halves of a kingdom feel about each other once it splits.
+pub const CIVIL_WAR_OPINION: isize = -80;
+
+// Consecutive turns the kingdom has ended with Happiness below `UNREST_HAPPINESS`.
+#[derive(Debug, Component, Clone, Copy, Default)]
+pub struct Unrest(pub usize);
+
+// What one side of a split kingdom starts with.
+type Share = Vec<(ResourceTypes, isize)>;
+
+// Each side's share of the kingdom's resources, loyalists first.
+fn split_resources(resources: &KingdomResources) -> (Share, Share) {
+    let mut loyal = Vec::new();
+    let mut rebel = Vec::new();
+    for resource in STARTING_RESOURCES.iter() {
+        let value = resources.get(*resource);
+        if *resource == ResourceTypes::Happiness {
+            loyal.push((*resource, value));
+            rebel.push((*resource, value + REBEL_ZEAL));
+        } else {
+            let share = value.max(0) * REBEL_SHARE / 100;
+            loyal.push((*resource, value - share));
+            rebel.push((*resource, share));
+        }
+    }
+    return (loyal, rebel);
+}
+
+fn populace(share: &Share) -> isize {
+    share
+        .iter()
+        .find(|(resource, _)| *resource == ResourceTypes::Populace)
+        .map_or(0, |(_, value)| *value)
+}
+
+fn split_devotion(devotion: &Devotion) -> (Devotion, Devotion) {
+    let mut loyal = Devotion::default();
+    let mut rebel = Devotion::default();
+    for (god, amount) in devotion.0.iter() {
+        let share = amount * REBEL_SHARE / 100;
+        loyal.shift(god, amount - share);
+        rebel.shift(god, share);
+    }
+    return (loyal, rebel);
+}
+
+// Everything the loyalists take with them when a kingdom splits.
+type Realm = (
+    Entity,
+    &'static KingdomID,
+    &'static Name,
+    &'static mut Unrest,
+    &'static Personality,
+    &'static Ruler,
+    &'static Culture,
+    &'static Traits,
+    &'static Devotion,
+    &'static Boons,
+    &'static Modifiers,
+    &'static UnlockedBlessings,
+);
+
+// The kingdoms that might rebel, with the resources and tasks they would split.
+#[derive(SystemParam)]
+pub struct RestlessKingdoms<'w, 's> {
+    kingdom_query: Query<'w, 's, Realm, With<Kingdom>>,
+    resource_query: Query<'w, 's, (&'static Resource, &'static ResourceType, &'static KingdomID)>,
+    task_query: Query<'w, 's, (Entity, &'static KingdomID), With<Task>>,
+}
+
+// Runs at the end of `TurnState::ApplyingChanges`, once the turn's Happiness is settled. A
+// rebellion replaces the kingdom with two new ones: the loyalists, who keep its name, ruler, tasks
+// and relations, and the rebels, who start afresh at war with them. A kingdom too small for both sides to
+// keep some Populace can't rebel.
+pub fn stir_unrest(
+    mut commands: Commands,
+    ai: Res<KingdomAi>,
+    generator: Res<KingdomGenerator>,
+    mut rng: ResMut<GameRng>,
+    mut relations: ResMut<Relations>,
+    mut restless: RestlessKingdoms,
+    mut log: ResMut<Log>,
+) {
+    let mut next_id = restless
+        .kingdom_query
+        .iter()
+        .map(|(_, KingdomID(id), _, _, _, _, _, _, _, _, _, _)| *id)
+        .max()
+        .unwrap_or(0)
+        + 1;
+    let mut taken: Vec<String> = restless
+        .kingdom_query
+        .iter()
+        .map(|(_, _, name, _, _, _, _, _, _, _, _, _)| name.to_string())
+        .collect();
+
+    // Kingdoms are visited in ID order so the same seed always makes the same choices.
+    let mut kingdoms: Vec<_> = restless.kingdom_query.iter_mut().collect();
+    kingdoms.sort_by_key(|(_, KingdomID(id), _, _, _, _, _, _, _, _, _, _)| *id);
+    let rng = rng.stream(RngStream::Unrest);
+    for (
+        entity,
+        kingdom,
+        name,
+        mut unrest,
+        personality,
+        ruler,
+        culture,
+        traits,
+        devotion,
+        boons,
+        modifiers,
+        unlocked,
+    ) in kingdoms
+    {
+        let resources = kingdom_snapshot(*kingdom, restless.resource_query.iter());
+        if resources.get(ResourceTypes::Happiness) >= UNREST_HAPPINESS {
+            unrest.0 = 0;
+            continue;
+        }
+        unrest.0 += 1;
+        if unrest.0 + 1 == UNREST_TURNS {
+            log.0.push((
+                format!("Mobs gather in the streets of {}.", name),
+                String::new(),
+            ));
+        }
+        let (loyal_resources, rebel_resources) = split_resources(&resources);
+        if unrest.0 < UNREST_TURNS
+            || populace(&loyal_resources) < REBEL_MIN_POPULACE
+            || populace(&rebel_resources) < REBEL_MIN_POPULACE
+            || !rng.gen_bool(REBELLION_CHANCE)
+        {
+            continue;
+        }
+
+        let loyal_id = KingdomID(next_id);
+        let rebel_id = KingdomID(next_id + 1);
+        next_id += 2;
+        let rebels = generator.generate(&taken, &ai.personalities, rng);
+        taken.push(rebels.name.clone());
+        let (loyal_devotion, rebel_devotion) = split_devotion(devotion);
+
+        commands.entity(entity).despawn_recursive();
+        let loyal = spawn_kingdom(&mut commands, loyal_id, name.to_string(), &loyal_resources);
+        commands
+            .entity(loyal)
+            .insert(personality.clone())
+            .insert(ruler.clone())
+            .insert(culture.clone())
+            .insert(traits.clone())
+            .insert(loyal_devotion)
+            .insert(boons.clone())
+            .insert(modifiers.clone())
+            .insert(unlocked.clone());
+        let rebel = spawn_kingdom(
+            &mut commands,
+            rebel_id,
+            rebels.name.clone(),
+            &rebel_resources,
+        );
+        commands
+            .entity(rebel)
+            .insert(Personality(rebels.personality))
+            .insert(Ruler(rebels.ruler))
+            .insert(culture.clone())
+            .insert(traits.clone())
+            .insert(rebel_devotion);
+        for (task, task_kingdom) in restless.task_query.iter() {
+            if task_kingdom == kingdom {
+                commands.entity(task).insert(loyal_id);
+            }
+        }
+        spawn_starting_tasks(&mut commands, rebel_id);
+        // The loyalists carry on the kingdom's treaties, trade and wars. `sync_relations` meets
+        // the rebels with everyone else as strangers.
+        for relation in relations.0.iter_mut() {
+            if relation.involves(*kingdom) {
+                let other = if relation.a == *kingdom {
+                    relation.b
+                } else {
+                    relation.a
+                };
+                let moved = Relation::new(other, loyal_id);
+                relation.a = moved.a;
+                relation.b = moved.b;
+            }
+        }
+        relations.0.push(Relation {
+            opinion: CIVIL_WAR_OPINION,
+            rivalry: true,
+            war: true,
+            ..Relation::new(loyal_id, rebel_id)
+        });
+
+        log.0.push((
+            format!(
+                "{} rises up against {}, and {} is torn in two.",
+                rebels.name, ruler.0, name
+            ),
+            format!("{}% of the realm goes with the rebels", REBEL_SHARE),
+        ));
+    }
+}
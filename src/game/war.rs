@@ -0,0 +1,162 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use rand::Rng;
+
+use super::diplomacy::Relations;
+use super::effect::Effect;
+use super::kingdom::{Kingdom, KingdomID};
+use super::resource::{
+    KingdomResources, Resource, ResourceType, ResourceTypes, STARTING_RESOURCES,
+};
+use super::rng::{GameRng, RngStream};
+use super::task::Task;
+use super::{kingdom_snapshot, ActionSource, ResourceAlterationEvent};
+
+// Every army fights somewhere between these fractions of its strength.
+pub const BATTLE_LUCK: (f32, f32) = (0.75, 1.25);
+// Percent of its Military each side loses in a battle.
+pub const LOSER_CASUALTIES: isize = 20;
+pub const WINNER_CASUALTIES: isize = 8;
+// Percent of the loser's Food and Industry the winner carries off.
+pub const SPOILS: isize = 15;
+// Opinion regained after each battle, as both sides tire of the war.
+pub const WAR_WEARINESS: isize = 4;
+// Percent of a conquered kingdom's resources its conqueror absorbs.
+pub const CONQUEST_SHARE: isize = 50;
+
+// How well a kingdom fights: its army, bolstered by faith and morale, plus a levy from the
+// populace.
+pub fn military_strength(resources: &KingdomResources) -> f32 {
+    let military = resources.get(ResourceTypes::Military).max(0) as f32;
+    let populace = resources.get(ResourceTypes::Populace).max(0) as f32;
+    let faith = resources.get(ResourceTypes::Faith).max(0) as f32;
+    let happiness = resources.get(ResourceTypes::Happiness).max(0) as f32;
+    return military * (1. + faith / 200.) * (0.5 + happiness / 100.) + populace / 5.;
+}
+
+fn percent_of(resources: &KingdomResources, resource: ResourceTypes, percent: isize) -> isize {
+    resources.get(resource).max(0) * percent / 100
+}
+
+// At least one soldier falls in every battle, so armies are whittled down to nothing.
+fn casualties(resources: &KingdomResources, percent: isize) -> isize {
+    let military = resources.get(ResourceTypes::Military).max(0);
+    return (military * percent / 100).max(1).min(military);
+}
+
+// Runs during `TurnState::CountingChanges`, after diplomacy has decided who is at war. A
+// defender with no army left that loses a battle is conquered.
+pub fn resolve_battles(
+    mut commands: Commands,
+    mut relations: ResMut<Relations>,
+    mut rng: ResMut<GameRng>,
+    kingdom_query: Query<(Entity, &KingdomID, &Name), With<Kingdom>>,
+    resource_query: Query<(&Resource, &ResourceType, &KingdomID)>,
+    task_query: Query<(Entity, &KingdomID), With<Task>>,
+    mut ev_resource_changes: EventWriter<ResourceAlterationEvent>,
+) {
+    let kingdoms: HashMap<KingdomID, (Entity, String)> = kingdom_query
+        .iter()
+        .map(|(entity, id, name)| (*id, (entity, name.to_string())))
+        .collect();
+    let rng = rng.stream(RngStream::Battles);
+    let mut conquered: Vec<KingdomID> = Vec::new();
+
+    for relation in relations.0.iter_mut().filter(|relation| relation.war) {
+        if conquered.contains(&relation.a) || conquered.contains(&relation.b) {
+            continue;
+        }
+        let (a, b) = (relation.a, relation.b);
+        let resources_a = kingdom_snapshot(a, resource_query.iter());
+        let resources_b = kingdom_snapshot(b, resource_query.iter());
+        let strength_a =
+            military_strength(&resources_a) * rng.gen_range(BATTLE_LUCK.0..BATTLE_LUCK.1);
+        let strength_b =
+            military_strength(&resources_b) * rng.gen_range(BATTLE_LUCK.0..BATTLE_LUCK.1);
+        let ((winner, winner_resources, winner_strength), (loser, loser_resources, loser_strength)) =
+            if strength_a >= strength_b {
+                ((a, &resources_a, strength_a), (b, &resources_b, strength_b))
+            } else {
+                ((b, &resources_b, strength_b), (a, &resources_a, strength_a))
+            };
+        let name = |kingdom: KingdomID| {
+            kingdoms
+                .get(&kingdom)
+                .map(|(_, name)| name.clone())
+                .unwrap_or_default()
+        };
+
+        let food = percent_of(loser_resources, ResourceTypes::Food, SPOILS);
+        let industry = percent_of(loser_resources, ResourceTypes::Industry, SPOILS);
+        ev_resource_changes.send(ResourceAlterationEvent {
+            source: ActionSource::World,
+            message: format!(
+                "{} defeats {} in battle ({:.0} against {:.0}).",
+                name(winner),
+                name(loser),
+                winner_strength,
+                loser_strength
+            ),
+            changes: vec![
+                (
+                    winner,
+                    Effect::Add(
+                        ResourceTypes::Military,
+                        -casualties(winner_resources, WINNER_CASUALTIES),
+                    ),
+                ),
+                (winner, Effect::Add(ResourceTypes::Food, food)),
+                (winner, Effect::Add(ResourceTypes::Industry, industry)),
+                (
+                    loser,
+                    Effect::Add(
+                        ResourceTypes::Military,
+                        -casualties(loser_resources, LOSER_CASUALTIES),
+                    ),
+                ),
+                (loser, Effect::Add(ResourceTypes::Food, -food)),
+                (loser, Effect::Add(ResourceTypes::Industry, -industry)),
+                (loser, Effect::Add(ResourceTypes::Happiness, -3)),
+            ],
+            cost: 0,
+        });
+        relation.shift(WAR_WEARINESS);
+
+        if loser_resources.get(ResourceTypes::Military) > 0 {
+            continue;
+        }
+        conquered.push(loser);
+        ev_resource_changes.send(ResourceAlterationEvent {
+            source: ActionSource::World,
+            message: format!(
+                "{} falls, and {} absorbs its lands.",
+                name(loser),
+                name(winner)
+            ),
+            changes: STARTING_RESOURCES
+                .iter()
+                .map(|resource| {
+                    (
+                        winner,
+                        Effect::Add(
+                            *resource,
+                            percent_of(loser_resources, *resource, CONQUEST_SHARE),
+                        ),
+                    )
+                })
+                .collect(),
+            cost: 0,
+        });
+    }
+
+    for kingdom in conquered {
+        if let Some((entity, _)) = kingdoms.get(&kingdom) {
+            commands.entity(*entity).despawn_recursive();
+        }
+        for (task, task_kingdom) in task_query.iter() {
+            if *task_kingdom == kingdom {
+                commands.entity(task).despawn();
+            }
+        }
+    }
+}
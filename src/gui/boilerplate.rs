@@ -155,6 +155,28 @@ pub fn button(style: ButtonTypeEnum) -> ButtonBundle {
             color: Color::rgb(0.8, 0.5, 0.4).into(),
             ..default()
         },
+        ButtonTypeEnum::TaskButton => ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(250.0), Val::Px(50.0)),
+                margin: Rect::all(Val::Auto),
+                justify_content: JustifyContent::SpaceEvenly,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                ..default()
+            },
+            ..default()
+        },
+        ButtonTypeEnum::SmallButton => ButtonBundle {
+            style: Style {
+                size: Size::new(Val::Px(70.0), Val::Px(50.0)),
+                margin: Rect::all(Val::Auto),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::Row,
+                ..default()
+            },
+            ..default()
+        },
     }
 }
 
@@ -172,6 +194,18 @@ pub fn text(asset_server: &Res<AssetServer>, text: String, style: DisplayTypeEnu
             ),
             ..default()
         },
+        DisplayTypeEnum::SmallText(_) => TextBundle {
+            text: Text::with_section(
+                text,
+                TextStyle {
+                    font: asset_server.load(FONT_NAME),
+                    font_size: 20.0,
+                    color: Color::rgb(0.9, 0.9, 0.9),
+                },
+                Default::default(),
+            ),
+            ..default()
+        },
         DisplayTypeEnum::ResourceText(_) => TextBundle {
             text: Text::with_section(
                 text,
@@ -207,16 +241,44 @@ pub fn resource_text(
     }
 }
 
+pub fn progress_bar_frame() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            size: Size::new(Val::Px(200.0), Val::Px(8.0)),
+            ..Default::default()
+        },
+        color: Color::rgb(0.15, 0.15, 0.15).into(),
+        ..Default::default()
+    }
+}
+
+pub fn progress_bar_fill(fraction: f32) -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            size: Size::new(Val::Percent(fraction * 100.), Val::Percent(100.)),
+            ..Default::default()
+        },
+        color: Color::rgb(0.5, 1., 0.6).into(),
+        ..Default::default()
+    }
+}
+
 pub fn display(
     asset_server: &Res<AssetServer>,
     style: DisplayTypeEnum,
     children: Vec<DisplayBundle>,
 ) -> FamilyBundle {
     let display = match style {
-        DisplayTypeEnum::StandardText(text) => DisplayBundle::DisplayText(TextBundle {
-            text: Text::with_section(text, STANDARD_TEXT_STYLE(asset_server), Default::default()),
-            ..default()
-        }),
+        DisplayTypeEnum::StandardText(text) | DisplayTypeEnum::SmallText(text) => {
+            DisplayBundle::DisplayText(TextBundle {
+                text: Text::with_section(
+                    text,
+                    STANDARD_TEXT_STYLE(asset_server),
+                    Default::default(),
+                ),
+                ..default()
+            })
+        }
         DisplayTypeEnum::ResourceText(resource) => {
             DisplayBundle::DisplayResource(ResourceTextBundle {
                 text: Text::with_section(
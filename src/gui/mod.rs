@@ -1,12 +1,32 @@
 pub mod boilerplate;
 
+use bevy::ecs::system::SystemParam;
 use bevy::text::Text2dBounds;
 use bevy::{prelude::*, ui::FocusPolicy};
+use strum::IntoEnumIterator;
 
 use self::boilerplate::*;
-use crate::game::kingdom::{self, Kingdom, KingdomID};
-use crate::game::resource::*;
-use crate::game::{GodActionEvent, Log, ResourceAlterationEvent, TurnState};
+use kingdom_click::game::ai::Personality;
+use kingdom_click::game::blessing::Blessings;
+use kingdom_click::game::diplomacy::Relations;
+use kingdom_click::game::effect::Effect;
+use kingdom_click::game::god::{GodStats, PurchaseUpgradeEvent, Upgrade};
+use kingdom_click::game::history::{RewindEvent, TurnHistory, UndoEvent};
+use kingdom_click::game::kingdom::{self, Culture, Kingdom, KingdomID, Ruler, UnlockedBlessings};
+use kingdom_click::game::miracle::{MiracleDef, MiracleEvent, Miracles, ResourceTarget};
+use kingdom_click::game::outcome::{GameOver, GameOverEvent};
+use kingdom_click::game::resource::*;
+use kingdom_click::game::rivals::{Devotion, RivalGods};
+use kingdom_click::game::save::{
+    read_save, save_exists, GameLoadedEvent, LoadGameEvent, NewGameEvent, SAVE_PATH,
+};
+use kingdom_click::game::task::{Task, TaskCompletedEvent, TaskProgressEvent};
+use kingdom_click::game::traits::{KingdomTraits, Traits};
+use kingdom_click::game::unrest::{Unrest, UNREST_TURNS};
+use kingdom_click::game::{
+    kingdom_snapshot, ActionSource, GodActionEvent, Log, ResourceAlterationEvent, Turn, TurnState,
+};
+
 use crate::AppState;
 
 pub const FONT_NAME: &str = "fonts/Rise of Kingdom.ttf";
@@ -25,15 +45,84 @@ pub struct MainMenuScreen;
 #[derive(Component)]
 pub struct MainMenuStartButton;
 
+#[derive(Component)]
+pub struct MainMenuContinueButton;
+
+#[derive(Component)]
+pub struct GameOverScreen;
+
+#[derive(Component)]
+pub struct GameOverRestartButton;
+
+#[derive(Component)]
+pub struct GameOverMenuButton;
+
 #[derive(Component)]
 pub struct GodActionButton;
 
 #[derive(Component)]
 pub struct ResourceInteractionButton {
-    pub interactions: Vec<(Entity, fn(usize) -> usize)>,
+    pub interactions: Vec<(KingdomID, Effect)>,
     pub message: String,
+    pub cost: isize,
 }
 
+#[derive(Component)]
+pub struct Tooltip(pub String);
+
+#[derive(Component)]
+pub struct TooltipText;
+
+#[derive(Component)]
+pub struct TaskButton {
+    pub kingdom: KingdomID,
+    pub task: String,
+}
+
+#[derive(Component)]
+pub struct TaskProgressBar(pub Entity);
+
+#[derive(Component)]
+pub struct ShopButton(pub Upgrade);
+
+#[derive(Component)]
+pub struct GodFaithText;
+
+#[derive(Component)]
+pub struct MiracleButton(pub String);
+
+// Names the miracle waiting for a target, if any.
+#[derive(Component)]
+pub struct MiracleBarText;
+
+// A miracle picked from the bar that still needs a kingdom or resource. The next resource button
+// clicked casts it there instead of blessing.
+#[derive(Debug, Clone, Default)]
+pub struct PendingMiracle(pub Option<String>);
+
+// Lists the modifiers active on a kingdom.
+#[derive(Component)]
+pub struct ModifierText(pub KingdomID);
+
+// Turns the sidebars to this page of kingdoms.
+#[derive(Component)]
+pub struct KingdomTab(pub usize);
+
+// Which pair of kingdoms the sidebars show.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SidebarPage(pub usize);
+
+pub const KINGDOMS_PER_PAGE: usize = 2;
+// Kingdom tabs per row above the log.
+const TABS_PER_ROW: usize = 8;
+
+#[derive(Component)]
+pub struct UndoButton;
+
+// Rewinds to the start of the turn this many turns back.
+#[derive(Component)]
+pub struct RewindButton(pub usize);
+
 #[derive(Component)]
 pub struct GameScreen;
 
@@ -43,6 +132,13 @@ pub struct ResourceDisplayText;
 #[derive(Component)]
 pub struct LogText;
 
+#[derive(Component)]
+pub struct RelationsText;
+
+// How the rival gods stand, and where they are worshipped.
+#[derive(Component)]
+pub struct RivalText;
+
 #[derive(Component)]
 pub struct ButtonType(ButtonTypeEnum);
 
@@ -60,11 +156,14 @@ impl Default for ResourceReference {
 pub enum ButtonTypeEnum {
     MainResourceButton,
     SettingsButton,
+    SmallButton,
+    TaskButton,
 }
 
 #[derive()]
 pub enum DisplayTypeEnum {
     StandardText(String),
+    SmallText(String),
     ResourceText(ResourceReference),
     ResourceIcon(ResourceTypes),
 }
@@ -102,9 +201,16 @@ pub struct GUIPlugin;
 
 impl Plugin for GUIPlugin {
     fn build(&self, app: &mut App) {
-        app.add_startup_system(ui_setup);
+        app.init_resource::<SidebarPage>()
+            .init_resource::<PendingMiracle>()
+            .add_startup_system(ui_setup);
         app.add_system_set(SystemSet::on_enter(AppState::MainMenu).with_system(spawn_main_menu))
-            .add_system_set(SystemSet::on_update(AppState::MainMenu).with_system(update_main_menu))
+            .add_system_set(
+                SystemSet::on_update(AppState::MainMenu)
+                    .with_system(update_main_menu)
+                    .with_system(continue_game)
+                    .with_system(enter_loaded_game),
+            )
             .add_system_set(SystemSet::on_exit(AppState::MainMenu).with_system(remove_main_menu));
 
         app.add_system_set(SystemSet::on_enter(AppState::Playing).with_system(spawn_game_screen))
@@ -117,11 +223,41 @@ impl Plugin for GUIPlugin {
                 SystemSet::on_update(AppState::Playing)
                     .before("godaction")
                     .with_system(resource_text_update)
-                    .with_system(do_resource_interaction),
+                    .with_system(do_resource_interaction)
+                    .with_system(do_task_interaction)
+                    .with_system(task_progress_update)
+                    .with_system(update_tooltip)
+                    .with_system(do_history_interaction)
+                    .with_system(update_rewind_buttons)
+                    .with_system(do_shop_interaction)
+                    .with_system(do_miracle_interaction)
+                    .with_system(update_miracle_bar)
+                    .with_system(do_tab_interaction)
+                    .with_system(update_shop)
+                    .with_system(update_modifier_text)
+                    .with_system(update_relations_text)
+                    .with_system(update_rival_text),
             )
+            .add_system_set_to_stage(
+                CoreStage::PostUpdate,
+                SystemSet::on_update(AppState::Playing).with_system(refresh_game_screen),
+            )
+            .add_system_set(SystemSet::on_update(AppState::Playing).with_system(end_game))
             .add_system_set(SystemSet::on_exit(AppState::Playing).with_system(remove_game_screen))
             .add_system_set(SystemSet::on_enter(TurnState::WaitingForGod).with_system(update_log));
 
+        app.add_system_set(
+            SystemSet::on_enter(AppState::GameOver).with_system(spawn_game_over_screen),
+        )
+        .add_system_set(
+            SystemSet::on_update(AppState::GameOver)
+                .with_system(update_game_over_screen)
+                .with_system(enter_loaded_game),
+        )
+        .add_system_set(
+            SystemSet::on_exit(AppState::GameOver).with_system(remove_game_over_screen),
+        );
+
         app.add_system(button_graphics_changes);
     }
 }
@@ -170,32 +306,100 @@ fn resource_text_update(
     mut res_query: Query<&Resource>,
 ) {
     for (mut text, ResourceReference(entity)) in text_query.iter_mut() {
-        let Resource {
+        // The resource may have just been despawned by a load that hasn't reached the screen yet.
+        if let Ok(Resource {
             value: val,
             change: _,
-        } = res_query.get_mut(*entity).unwrap();
-        text.sections[0].value = val.to_string();
+        }) = res_query.get_mut(*entity)
+        {
+            text.sections[0].value = val.to_string();
+        }
     }
 }
 
 fn do_resource_interaction(
     // mut res_query: Query<(Entity, &mut Resource, &ResourceType, &KingdomID)>,
-    mut button_query: Query<(&Interaction, &ResourceInteractionButton), Changed<Interaction>>,
+    mut button_query: Query<
+        (
+            &Interaction,
+            &ResourceInteractionButton,
+            Option<&ResourceReference>,
+        ),
+        Changed<Interaction>,
+    >,
+    kingdom_query: Query<&KingdomID, With<Kingdom>>,
+    res_query: Query<&ResourceType>,
+    god: Res<GodStats>,
+    miracles: Res<Miracles>,
+    pending: Res<PendingMiracle>,
     mut ev_interactions: EventWriter<ResourceAlterationEvent>,
+    mut ev_miracle: EventWriter<MiracleEvent>,
 ) {
+    let kingdoms: Vec<KingdomID> = kingdom_query.iter().cloned().collect();
+    let miracle = pending_miracle(&pending, &miracles);
     for (
         interaction,
         ResourceInteractionButton {
             interactions,
             message,
+            cost,
         },
+        reference,
     ) in button_query.iter_mut()
     {
+        match (*interaction, miracle) {
+            (Interaction::Clicked, Some(miracle)) => {
+                if can_cast_on(miracle, &god, reference) {
+                    ev_miracle.send(MiracleEvent {
+                        miracle: miracle.name.clone(),
+                        kingdom: interactions.first().map(|(kingdom, _)| *kingdom),
+                        resource: reference
+                            .and_then(|ResourceReference(entity)| res_query.get(*entity).ok())
+                            .map(|ResourceType(resource_type)| *resource_type),
+                    });
+                }
+            }
+            (Interaction::Clicked, None) if god.can_afford(god.miracle_cost(*cost)) => {
+                ev_interactions.send(god.empower(
+                    ResourceAlterationEvent {
+                        source: ActionSource::Player,
+                        message: (*message).to_string(),
+                        changes: interactions.clone(),
+                        cost: *cost,
+                    },
+                    &kingdoms,
+                ));
+            }
+            _ => {}
+        }
+    }
+}
+
+fn pending_miracle<'a>(pending: &PendingMiracle, miracles: &'a Miracles) -> Option<&'a MiracleDef> {
+    pending.0.as_ref().and_then(|name| miracles.get(name))
+}
+
+// Whether clicking a resource button casts the pending miracle. Only resource buttons (rather
+// than unlocked blessings) can pick the resource for miracles that need one.
+fn can_cast_on(
+    miracle: &MiracleDef,
+    god: &GodStats,
+    reference: Option<&ResourceReference>,
+) -> bool {
+    miracle.ready(god) && (miracle.resources != ResourceTarget::Chosen || reference.is_some())
+}
+
+fn do_task_interaction(
+    button_query: Query<(&Interaction, &TaskButton), Changed<Interaction>>,
+    mut ev_progress: EventWriter<TaskProgressEvent>,
+) {
+    for (interaction, TaskButton { kingdom, task }) in button_query.iter() {
         match *interaction {
             Interaction::Clicked => {
-                ev_interactions.send(ResourceAlterationEvent {
-                    message: (*message).to_string(),
-                    changes: interactions.clone(),
+                ev_progress.send(TaskProgressEvent {
+                    kingdom: *kingdom,
+                    task: task.clone(),
+                    amount: 1,
                 });
             }
             _ => {}
@@ -203,14 +407,363 @@ fn do_resource_interaction(
     }
 }
 
+fn task_progress_update(
+    mut bar_query: Query<(&mut Style, &TaskProgressBar)>,
+    task_query: Query<&Task, Changed<Task>>,
+) {
+    for (mut style, TaskProgressBar(entity)) in bar_query.iter_mut() {
+        if let Ok(task) = task_query.get(*entity) {
+            style.size.width = Val::Percent(task.fraction() * 100.);
+        }
+    }
+}
+
+fn update_tooltip(
+    button_query: Query<(&Interaction, &Tooltip), Changed<Interaction>>,
+    mut tooltip_query: Query<&mut Text, With<TooltipText>>,
+) {
+    for (interaction, Tooltip(description)) in button_query.iter() {
+        let shown = match *interaction {
+            Interaction::Hovered | Interaction::Clicked => description.clone(),
+            Interaction::None => "".to_string(),
+        };
+        for mut text in tooltip_query.iter_mut() {
+            text.sections[0].value = shown.clone();
+        }
+    }
+}
+
+fn do_history_interaction(
+    undo_query: Query<&Interaction, (Changed<Interaction>, With<UndoButton>)>,
+    rewind_query: Query<(&Interaction, &RewindButton), Changed<Interaction>>,
+    history: Res<TurnHistory>,
+    mut ev_undo: EventWriter<UndoEvent>,
+    mut ev_rewind: EventWriter<RewindEvent>,
+) {
+    for interaction in undo_query.iter() {
+        match *interaction {
+            Interaction::Clicked => ev_undo.send(UndoEvent),
+            _ => {}
+        }
+    }
+    for (interaction, RewindButton(turns_back)) in rewind_query.iter() {
+        match *interaction {
+            Interaction::Clicked => {
+                let turns = history.turns();
+                if let Some(turn) = turns.iter().rev().nth(*turns_back) {
+                    ev_rewind.send(RewindEvent(*turn));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn do_shop_interaction(
+    button_query: Query<(&Interaction, &ShopButton), Changed<Interaction>>,
+    god: Res<GodStats>,
+    mut ev_purchase: EventWriter<PurchaseUpgradeEvent>,
+) {
+    for (interaction, ShopButton(upgrade)) in button_query.iter() {
+        match *interaction {
+            Interaction::Clicked => {
+                if upgrade
+                    .cost(&god)
+                    .map_or(false, |cost| god.can_afford(cost))
+                {
+                    ev_purchase.send(PurchaseUpgradeEvent(*upgrade));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// Miracles that need no target are cast straight away. The rest wait for a kingdom's resource to
+// be clicked; clicking them again cancels.
+fn do_miracle_interaction(
+    button_query: Query<(&Interaction, &MiracleButton), Changed<Interaction>>,
+    god: Res<GodStats>,
+    miracles: Res<Miracles>,
+    mut pending: ResMut<PendingMiracle>,
+    mut ev_miracle: EventWriter<MiracleEvent>,
+) {
+    for (interaction, MiracleButton(name)) in button_query.iter() {
+        let miracle = match miracles.get(name) {
+            Some(miracle) => miracle,
+            None => continue,
+        };
+        match *interaction {
+            Interaction::Clicked if pending.0.as_ref() == Some(name) => pending.0 = None,
+            Interaction::Clicked if miracle.ready(&god) => {
+                if miracle.needs_target() {
+                    pending.0 = Some(name.clone());
+                } else {
+                    ev_miracle.send(MiracleEvent {
+                        miracle: name.clone(),
+                        kingdom: None,
+                        resource: None,
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn miracle_label(miracle: &MiracleDef, god: &GodStats, pending: &PendingMiracle) -> String {
+    let label = match god.cooldown(&miracle.name) {
+        0 => format!("{} ({})", miracle.name, god.miracle_cost(miracle.cost)),
+        turns => format!("{} ({} turns)", miracle.name, turns),
+    };
+    if pending.0.as_ref() == Some(&miracle.name) {
+        return format!("> {} <", label);
+    }
+    return label;
+}
+
+fn miracle_bar_title(pending: &PendingMiracle) -> String {
+    match &pending.0 {
+        Some(name) => format!("Miracles: choose where to cast {}", name),
+        None => "Miracles".to_string(),
+    }
+}
+
+fn update_miracle_bar(
+    god: Res<GodStats>,
+    miracles: Res<Miracles>,
+    pending: Res<PendingMiracle>,
+    mut title_query: Query<&mut Text, With<MiracleBarText>>,
+    button_query: Query<(&MiracleButton, &Children)>,
+    mut text_query: Query<&mut Text, Without<MiracleBarText>>,
+) {
+    if !god.is_changed() && !pending.is_changed() {
+        return;
+    }
+    for mut text in title_query.iter_mut() {
+        text.sections[0].value = miracle_bar_title(&pending);
+    }
+    for (MiracleButton(name), children) in button_query.iter() {
+        let miracle = match miracles.get(name) {
+            Some(miracle) => miracle,
+            None => continue,
+        };
+        let label = miracle_label(miracle, &god, &pending);
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.sections[0].value = label.clone();
+            }
+        }
+    }
+}
+
+fn do_tab_interaction(
+    button_query: Query<(&Interaction, &KingdomTab), Changed<Interaction>>,
+    mut page: ResMut<SidebarPage>,
+) {
+    for (interaction, KingdomTab(tab_page)) in button_query.iter() {
+        match *interaction {
+            Interaction::Clicked if page.0 != *tab_page => page.0 = *tab_page,
+            _ => {}
+        }
+    }
+}
+
+fn update_shop(
+    god: Res<GodStats>,
+    mut faith_query: Query<&mut Text, With<GodFaithText>>,
+    button_query: Query<(&ShopButton, &Children)>,
+    mut text_query: Query<&mut Text, Without<GodFaithText>>,
+) {
+    if !god.is_changed() {
+        return;
+    }
+    for mut text in faith_query.iter_mut() {
+        text.sections[0].value = format!("Faith: {}", god.faith);
+    }
+    for (ShopButton(upgrade), children) in button_query.iter() {
+        let label = match upgrade.cost(&god) {
+            Some(cost) => format!("{} ({})", upgrade.name(), cost),
+            None => format!("{} (max)", upgrade.name()),
+        };
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                text.sections[0].value = label.clone();
+            }
+        }
+    }
+}
+
+// Also warns of unrest, which can end in rebellion.
+fn update_modifier_text(
+    kingdom_query: Query<(&KingdomID, &Modifiers, &Unrest), With<Kingdom>>,
+    mut text_query: Query<(&ModifierText, &mut Text)>,
+) {
+    for (ModifierText(kingdom), mut text) in text_query.iter_mut() {
+        let mut lines: Vec<String> = Vec::new();
+        for (_, modifiers, Unrest(turns)) in
+            kingdom_query.iter().filter(|(id, _, _)| *id == kingdom)
+        {
+            if *turns > 0 {
+                lines.push(format!("Unrest: {} of {} turns", turns, UNREST_TURNS));
+            }
+            lines.extend(
+                modifiers
+                    .0
+                    .iter()
+                    .map(|modifier| format!("{}: {}", modifier.source, modifier.describe())),
+            );
+        }
+        let label = lines.join("\n");
+        if text.sections[0].value != label {
+            text.sections[0].value = label;
+        }
+    }
+}
+
+// Only the relations of the kingdoms on the current page, so many realms don't flood the screen.
+fn update_relations_text(
+    relations: Res<Relations>,
+    page: Res<SidebarPage>,
+    kingdom_query: Query<(&KingdomID, &Name), With<Kingdom>>,
+    mut text_query: Query<&mut Text, With<RelationsText>>,
+) {
+    let visible = visible_kingdoms(kingdom_query.iter().map(|(id, _)| *id).collect(), page.0);
+    let name = |kingdom: KingdomID| {
+        kingdom_query
+            .iter()
+            .find(|(id, _)| **id == kingdom)
+            .map(|(_, name)| name.to_string())
+            .unwrap_or_default()
+    };
+    let label = relations
+        .0
+        .iter()
+        .filter(|relation| visible.iter().any(|kingdom| relation.involves(*kingdom)))
+        .map(|relation| {
+            format!(
+                "{} & {}: {:+} ({})",
+                name(relation.a),
+                name(relation.b),
+                relation.opinion,
+                relation.status()
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    for mut text in text_query.iter_mut() {
+        if text.sections[0].value != label {
+            text.sections[0].value = label.clone();
+        }
+    }
+}
+
+fn update_rival_text(
+    rivals: Res<RivalGods>,
+    kingdom_query: Query<(&KingdomID, &Name, &Devotion), With<Kingdom>>,
+    mut text_query: Query<&mut Text, With<RivalText>>,
+) {
+    let mut kingdoms: Vec<(&KingdomID, &Name, &Devotion)> = kingdom_query.iter().collect();
+    kingdoms.sort_by_key(|(KingdomID(id), _, _)| *id);
+    let label = rivals
+        .0
+        .iter()
+        .map(|rival| {
+            let worship = kingdoms
+                .iter()
+                .filter(|(_, _, devotion)| devotion.of(&rival.name) > 0)
+                .map(|(_, name, devotion)| format!("{} ({})", name, devotion.of(&rival.name)))
+                .collect::<Vec<String>>();
+            if worship.is_empty() {
+                format!("{}: {} faith", rival.name, rival.faith)
+            } else {
+                format!(
+                    "{}: {} faith, worshipped in {}",
+                    rival.name,
+                    rival.faith,
+                    worship.join(", ")
+                )
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    for mut text in text_query.iter_mut() {
+        if text.sections[0].value != label {
+            text.sections[0].value = label.clone();
+        }
+    }
+}
+
+// The kingdoms shown on `page`, falling back to the last page when conquests have emptied it.
+fn visible_kingdoms(mut kingdoms: Vec<KingdomID>, page: usize) -> Vec<KingdomID> {
+    kingdoms.sort_by_key(|KingdomID(id)| *id);
+    let page = page.min(kingdoms.len().saturating_sub(1) / KINGDOMS_PER_PAGE);
+    return kingdoms
+        .into_iter()
+        .skip(page * KINGDOMS_PER_PAGE)
+        .take(KINGDOMS_PER_PAGE)
+        .collect();
+}
+
+fn update_rewind_buttons(
+    history: Res<TurnHistory>,
+    button_query: Query<(&RewindButton, &Children)>,
+    mut text_query: Query<&mut Text>,
+) {
+    let turns = history.turns();
+    for (RewindButton(turns_back), children) in button_query.iter() {
+        let label = match turns.iter().rev().nth(*turns_back) {
+            Some(turn) => turn.to_string(),
+            None => "-".to_string(),
+        };
+        for child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(*child) {
+                if text.sections[0].value != label {
+                    text.sections[0].value = label.clone();
+                }
+            }
+        }
+    }
+}
+
 fn send_god_action(
-    mut button_query: Query<&Interaction, (Changed<Interaction>, With<GodActionButton>)>,
+    mut button_query: Query<
+        (
+            &Interaction,
+            Option<&ResourceInteractionButton>,
+            Option<&ResourceReference>,
+            Option<&ShopButton>,
+            Option<&MiracleButton>,
+        ),
+        (Changed<Interaction>, With<GodActionButton>),
+    >,
+    god: Res<GodStats>,
+    miracles: Res<Miracles>,
+    mut pending: ResMut<PendingMiracle>,
     mut ev_interactions: EventWriter<GodActionEvent>,
 ) {
-    for interaction in button_query.iter_mut() {
+    for (interaction, blessing, reference, shop, miracle) in button_query.iter_mut() {
+        // Clicking something the god can't pay for doesn't end the turn, and neither does picking
+        // a miracle that still needs a target.
+        let affordable = match (blessing, shop, miracle) {
+            (_, Some(ShopButton(upgrade)), _) => upgrade
+                .cost(&god)
+                .map_or(false, |cost| god.can_afford(cost)),
+            (_, _, Some(MiracleButton(name))) => miracles.get(name).map_or(false, |miracle| {
+                !miracle.needs_target() && miracle.ready(&god)
+            }),
+            (Some(blessing), _, _) => match pending_miracle(&pending, &miracles) {
+                Some(miracle) => can_cast_on(miracle, &god, reference),
+                None => god.can_afford(god.miracle_cost(blessing.cost)),
+            },
+            _ => true,
+        };
         match *interaction {
-            Interaction::Clicked => {
+            Interaction::Clicked if affordable => {
                 ev_interactions.send(GodActionEvent);
+                if pending.0.is_some() {
+                    pending.0 = None;
+                }
             }
             _ => {}
         }
@@ -235,16 +788,117 @@ fn update_log(mut log_display_query: Query<(&LogText, &mut Text)>, log_text: Res
 //       Game Screen Changing Functions
 // // // // // // // // // // // // // //
 
-fn spawn_game_screen(
+#[derive(SystemParam)]
+pub struct GameScreenParams<'w, 's> {
+    asset_server: Res<'w, AssetServer>,
+    blessings: Res<'w, Blessings>,
+    god: Res<'w, GodStats>,
+    miracles: Res<'w, Miracles>,
+    pending: Res<'w, PendingMiracle>,
+    kingdom_query: Query<
+        'w,
+        's,
+        (
+            &'static KingdomID,
+            &'static Name,
+            &'static Personality,
+            &'static UnlockedBlessings,
+            &'static Ruler,
+            &'static Culture,
+        ),
+        With<Kingdom>,
+    >,
+    resource_query: Query<'w, 's, (Entity, &'static ResourceType, &'static KingdomID)>,
+    task_query: Query<'w, 's, (Entity, &'static Task, &'static KingdomID)>,
+    kingdom_traits: Res<'w, KingdomTraits>,
+    trait_query: Query<'w, 's, (&'static KingdomID, &'static Traits), With<Kingdom>>,
+    page: Res<'w, SidebarPage>,
+    log: Res<'w, Log>,
+}
+
+fn spawn_game_screen(mut commands: Commands, params: GameScreenParams) {
+    build_game_screen(&mut commands, &params);
+}
+
+// Loading or rewinding respawns every kingdom, kingdoms are conquered or split by rebellions,
+// finished tasks disappear and chained ones or unlocked blessings appear, and the god turns to
+// another page of kingdoms, so the screen has to be rebuilt around them. Runs in `CoreStage::PostUpdate`, once the commands
+// that changed them have been applied.
+fn refresh_game_screen(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    kingdom_query: Query<(&KingdomID, &Name), With<Kingdom>>,
-    resource_query: Query<(Entity, &ResourceType, &KingdomID)>,
+    mut ev_loaded: EventReader<GameLoadedEvent>,
+    mut ev_completed: EventReader<TaskCompletedEvent>,
+    added_tasks: Query<(), Added<Task>>,
+    added_kingdoms: Query<(), Added<Kingdom>>,
+    changed_blessings: Query<(), Changed<UnlockedBlessings>>,
+    removed_kingdoms: RemovedComponents<Kingdom>,
+    screen_query: Query<Entity, With<GameScreen>>,
+    params: GameScreenParams,
 ) {
-    let mut kingdom_iter = kingdom_query.iter();
+    let changed = added_tasks.iter().count()
+        + added_kingdoms.iter().count()
+        + changed_blessings.iter().count()
+        + removed_kingdoms.iter().count();
+    if ev_loaded.iter().count() + ev_completed.iter().count() + changed == 0
+        && !params.page.is_changed()
+    {
+        return;
+    }
+    for screen in screen_query.iter() {
+        commands.entity(screen).despawn_recursive();
+    }
+    build_game_screen(&mut commands, &params);
+}
+
+// The ruler, culture and personality under a kingdom's name, one to a line.
+fn kingdom_description(ruler: &Ruler, culture: &Culture, personality: &Personality) -> String {
+    return [&ruler.0, &culture.0, &personality.0]
+        .iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.to_string())
+        .collect::<Vec<String>>()
+        .join("\n");
+}
+
+// The log panel's text. `update_log` only refreshes it as each turn starts, so a rebuilt screen
+// fills it in itself.
+fn log_text(log: &Log) -> String {
+    if log.0.is_empty() {
+        return "Your journey begins.".to_string();
+    }
+    return log
+        .0
+        .iter()
+        .map(|(message, detail)| format!("{}\n{}", message, detail))
+        .collect::<Vec<String>>()
+        .join("\n");
+}
+
+fn build_game_screen(commands: &mut Commands, params: &GameScreenParams) {
+    let asset_server = &params.asset_server;
+    let blessings = &params.blessings;
+    let god = &params.god;
+    let resource_query = &params.resource_query;
+    let task_query = &params.task_query;
+    let kingdom_traits = &params.kingdom_traits;
+    let trait_query = &params.trait_query;
+    let mut kingdoms: Vec<_> = params.kingdom_query.iter().collect();
+    kingdoms.sort_by_key(|(KingdomID(id), _, _, _, _, _)| *id);
+    let visible = visible_kingdoms(
+        kingdoms.iter().map(|(id, _, _, _, _, _)| **id).collect(),
+        params.page.0,
+    );
+    let mut kingdom_iter = kingdoms
+        .iter()
+        .filter(|(id, _, _, _, _, _)| visible.contains(*id))
+        .cloned();
 
     let kingdom_sidebar_generator =
-        |parent: &mut ChildBuilder<'_, '_, '_>, id: &usize, name: &Name| {
+        |parent: &mut ChildBuilder<'_, '_, '_>,
+         id: &usize,
+         name: &Name,
+         description: &str,
+         unlocked: &UnlockedBlessings| {
             parent
                 .spawn_bundle(column_perc(25., 100.))
                 .with_children(|parent| {
@@ -254,36 +908,59 @@ fn spawn_game_screen(
                         format!("{}", name).to_string(),
                         DisplayTypeEnum::StandardText(format!("{}", name).to_string()),
                     ));
+                    parent
+                        .spawn_bundle(text(
+                            &asset_server,
+                            String::new(),
+                            DisplayTypeEnum::SmallText(String::new()),
+                        ))
+                        .insert(ModifierText(KingdomID(*id)));
+                    if !description.is_empty() {
+                        parent.spawn_bundle(text(
+                            &asset_server,
+                            description.to_string(),
+                            DisplayTypeEnum::SmallText(description.to_string()),
+                        ));
+                    }
+                    // Traits, explained when hovered
+                    for (_, Traits(traits)) in trait_query
+                        .iter()
+                        .filter(|(KingdomID(trait_kingdom), _)| trait_kingdom == id)
+                    {
+                        for definition in traits.iter().filter_map(|name| kingdom_traits.get(name))
+                        {
+                            parent
+                                .spawn_bundle(text(
+                                    &asset_server,
+                                    definition.name.clone(),
+                                    DisplayTypeEnum::SmallText(definition.name.clone()),
+                                ))
+                                .insert(Interaction::default())
+                                .insert(Tooltip(definition.describe()));
+                        }
+                    }
                     for (entity, ResourceType(resource_type), KingdomID(resource_kingdom)) in
                         resource_query.iter()
                     {
                         if resource_kingdom == id {
+                            let blessing = match blessings.for_resource(*resource_type) {
+                                Some(blessing) => blessing,
+                                None => continue,
+                            };
                             parent
                                 .spawn_bundle(button(ButtonTypeEnum::MainResourceButton))
                                 .insert(GodActionButton)
                                 .insert(ResourceReference(entity))
                                 .insert(ResourceInteractionButton {
-                                    interactions: vec![(entity, |resource| resource + 1)],
-                                    message: match resource_type {
-                                        ResourceTypes::Food => "You bless the fields.",
-                                        ResourceTypes::Industry => {
-                                            "You inspire the laborers with vigor."
-                                        }
-                                        ResourceTypes::Faith => {
-                                            "Minor miracles cultivate the people's faith."
-                                        }
-                                        ResourceTypes::Populace => {
-                                            "Blessings of fertility bolster the populace."
-                                        }
-                                        ResourceTypes::Military => {
-                                            "Visions of glorious crusades dance in their heads."
-                                        }
-                                        ResourceTypes::Happiness => {
-                                            "You help an old woman find her keys."
-                                        }
-                                    }
-                                    .to_string(),
+                                    interactions: blessing
+                                        .effects
+                                        .iter()
+                                        .map(|effect| (KingdomID(*id), effect.clone()))
+                                        .collect(),
+                                    message: blessing.message.clone(),
+                                    cost: blessing.cost,
                                 })
+                                .insert(Tooltip(blessing.describe()))
                                 .with_children(|button| {
                                     button.spawn_bundle(text(
                                         &asset_server,
@@ -302,24 +979,123 @@ fn spawn_game_screen(
                             // });
                         }
                     }
+
+                    // Blessings unlocked by tasks
+                    for blessing in unlocked
+                        .0
+                        .iter()
+                        .filter_map(|blessing_name| blessings.by_name(blessing_name))
+                    {
+                        parent
+                            .spawn_bundle(button(ButtonTypeEnum::TaskButton))
+                            .insert(GodActionButton)
+                            .insert(ResourceInteractionButton {
+                                interactions: blessing
+                                    .effects
+                                    .iter()
+                                    .map(|effect| (KingdomID(*id), effect.clone()))
+                                    .collect(),
+                                message: blessing.message.clone(),
+                                cost: blessing.cost,
+                            })
+                            .insert(Tooltip(blessing.describe()))
+                            .with_children(|button| {
+                                button.spawn_bundle(text(
+                                    &asset_server,
+                                    blessing.title(),
+                                    DisplayTypeEnum::SmallText(blessing.title()),
+                                ));
+                            });
+                    }
+
+                    // Task Panel
+                    let mut tasks: Vec<(Entity, &Task)> = task_query
+                        .iter()
+                        .filter(|(_, task, KingdomID(task_kingdom))| {
+                            task_kingdom == id && !task.is_complete()
+                        })
+                        .map(|(entity, task, _)| (entity, task))
+                        .collect();
+                    tasks.sort_by_key(|(_, task)| task.name);
+                    for (entity, task) in tasks {
+                        parent
+                            .spawn_bundle(button(ButtonTypeEnum::TaskButton))
+                            .insert(GodActionButton)
+                            .insert(TaskButton {
+                                kingdom: KingdomID(*id),
+                                task: task.name.to_string(),
+                            })
+                            .insert(Tooltip(task.description.to_string()))
+                            .with_children(|button| {
+                                button.spawn_bundle(text(
+                                    &asset_server,
+                                    task.name.to_string(),
+                                    DisplayTypeEnum::SmallText(task.name.to_string()),
+                                ));
+                                button
+                                    .spawn_bundle(progress_bar_frame())
+                                    .with_children(|frame| {
+                                        frame
+                                            .spawn_bundle(progress_bar_fill(task.fraction()))
+                                            .insert(TaskProgressBar(entity));
+                                    });
+                            });
+                    }
                 });
         };
     commands // Spawn columns
         .spawn_bundle(row_perc(100., -1.))
         .insert(GameScreen)
         .with_children(|parent| {
-            // Kingdom 1 Sidebar
-            let (KingdomID(id), name) = kingdom_iter.next().unwrap();
-            kingdom_sidebar_generator(parent, &id, &name);
+            // Left Sidebar
+            match kingdom_iter.next() {
+                Some((KingdomID(id), name, personality, unlocked, ruler, culture)) => {
+                    let description = kingdom_description(ruler, culture, personality);
+                    kingdom_sidebar_generator(parent, &id, &name, &description, &unlocked)
+                }
+                // Conquered kingdoms and odd pages leave an empty column behind.
+                None => {
+                    parent.spawn_bundle(column_perc(25., 100.));
+                }
+            }
 
             // Log
             parent
                 .spawn_bundle(column_perc(50., 100.))
                 .with_children(|parent| {
+                    // Kingdom tabs, only needed once the sidebars can't show every kingdom.
+                    if kingdoms.len() > KINGDOMS_PER_PAGE {
+                        for (row_index, row_kingdoms) in kingdoms.chunks(TABS_PER_ROW).enumerate() {
+                            parent
+                                .spawn_bundle(row_perc(100., -1.))
+                                .with_children(|row| {
+                                    for (column, (kingdom, name, _, _, _, _)) in
+                                        row_kingdoms.iter().enumerate()
+                                    {
+                                        let position = row_index * TABS_PER_ROW + column;
+                                        let label = if visible.contains(*kingdom) {
+                                            format!("[{}]", kingdom.0)
+                                        } else {
+                                            kingdom.0.to_string()
+                                        };
+                                        row.spawn_bundle(button(ButtonTypeEnum::SmallButton))
+                                            .insert(KingdomTab(position / KINGDOMS_PER_PAGE))
+                                            .insert(Tooltip(name.to_string()))
+                                            .with_children(|button| {
+                                                button.spawn_bundle(text(
+                                                    &asset_server,
+                                                    label.clone(),
+                                                    DisplayTypeEnum::StandardText(label),
+                                                ));
+                                            });
+                                    }
+                                });
+                        }
+                    }
                     parent
                         .spawn_bundle(TextBundle {
                             text: Text::with_section(
-                                "Your journey begins.",
+                                log_text(&params.log),
                                 TextStyle {
                                     font: asset_server.load(FONT_NAME),
                                     font_size: 30.0,
@@ -330,11 +1106,151 @@ fn spawn_game_screen(
                             ..default()
                         })
                         .insert(LogText);
+                    parent
+                        .spawn_bundle(TextBundle {
+                            text: Text::with_section(
+                                "",
+                                TextStyle {
+                                    font: asset_server.load(FONT_NAME),
+                                    font_size: 25.0,
+                                    color: Color::rgb(0.6, 0.75, 0.9),
+                                },
+                                Default::default(),
+                            ),
+                            ..default()
+                        })
+                        .insert(RelationsText);
+                    parent
+                        .spawn_bundle(TextBundle {
+                            text: Text::with_section(
+                                "",
+                                TextStyle {
+                                    font: asset_server.load(FONT_NAME),
+                                    font_size: 25.0,
+                                    color: Color::rgb(0.85, 0.5, 0.5),
+                                },
+                                Default::default(),
+                            ),
+                            ..default()
+                        })
+                        .insert(RivalText);
+                    parent
+                        .spawn_bundle(TextBundle {
+                            text: Text::with_section(
+                                "",
+                                TextStyle {
+                                    font: asset_server.load(FONT_NAME),
+                                    font_size: 25.0,
+                                    color: Color::rgb(0.9, 0.8, 0.5),
+                                },
+                                Default::default(),
+                            ),
+                            ..default()
+                        })
+                        .insert(TooltipText);
+                    parent
+                        .spawn_bundle(row_perc(100., -1.))
+                        .with_children(|row| {
+                            row.spawn_bundle(button(ButtonTypeEnum::SettingsButton))
+                                .insert(UndoButton)
+                                .with_children(|button| {
+                                    button.spawn_bundle(text(
+                                        &asset_server,
+                                        "Undo".to_string(),
+                                        DisplayTypeEnum::StandardText("Undo".to_string()),
+                                    ));
+                                });
+                            row.spawn_bundle(text(
+                                &asset_server,
+                                "Rewind to".to_string(),
+                                DisplayTypeEnum::StandardText("Rewind to".to_string()),
+                            ));
+                            for turns_back in 2..=4 {
+                                row.spawn_bundle(button(ButtonTypeEnum::SmallButton))
+                                    .insert(RewindButton(turns_back))
+                                    .with_children(|button| {
+                                        button.spawn_bundle(text(
+                                            &asset_server,
+                                            "-".to_string(),
+                                            DisplayTypeEnum::StandardText("-".to_string()),
+                                        ));
+                                    });
+                            }
+                        });
+
+                    // Shop
+                    parent
+                        .spawn_bundle(text(
+                            &asset_server,
+                            format!("Faith: {}", god.faith),
+                            DisplayTypeEnum::StandardText(format!("Faith: {}", god.faith)),
+                        ))
+                        .insert(GodFaithText);
+                    let upgrades: Vec<Upgrade> = Upgrade::iter().collect();
+                    for pair in upgrades.chunks(2) {
+                        parent
+                            .spawn_bundle(row_perc(100., -1.))
+                            .with_children(|row| {
+                                for upgrade in pair {
+                                    let label = match upgrade.cost(god) {
+                                        Some(cost) => format!("{} ({})", upgrade.name(), cost),
+                                        None => format!("{} (max)", upgrade.name()),
+                                    };
+                                    row.spawn_bundle(button(ButtonTypeEnum::TaskButton))
+                                        .insert(GodActionButton)
+                                        .insert(ShopButton(*upgrade))
+                                        .insert(Tooltip(upgrade.description().to_string()))
+                                        .with_children(|button| {
+                                            button.spawn_bundle(text(
+                                                &asset_server,
+                                                label.clone(),
+                                                DisplayTypeEnum::SmallText(label),
+                                            ));
+                                        });
+                                }
+                            });
+                    }
+
+                    // Miracle bar
+                    parent
+                        .spawn_bundle(text(
+                            &asset_server,
+                            miracle_bar_title(&params.pending),
+                            DisplayTypeEnum::StandardText(miracle_bar_title(&params.pending)),
+                        ))
+                        .insert(MiracleBarText);
+                    for pair in params.miracles.0.chunks(2) {
+                        parent
+                            .spawn_bundle(row_perc(100., -1.))
+                            .with_children(|row| {
+                                for miracle in pair {
+                                    let label = miracle_label(miracle, god, &params.pending);
+                                    row.spawn_bundle(button(ButtonTypeEnum::TaskButton))
+                                        .insert(GodActionButton)
+                                        .insert(MiracleButton(miracle.name.clone()))
+                                        .insert(Tooltip(miracle.describe()))
+                                        .with_children(|button| {
+                                            button.spawn_bundle(text(
+                                                &asset_server,
+                                                label.clone(),
+                                                DisplayTypeEnum::SmallText(label),
+                                            ));
+                                        });
+                                }
+                            });
+                    }
                 });
 
-            // Kingdom 2 Sidebar
-            let (KingdomID(id), name) = kingdom_iter.next().unwrap();
-            kingdom_sidebar_generator(parent, &id, &name);
+            // Right Sidebar
+            match kingdom_iter.next() {
+                Some((KingdomID(id), name, personality, unlocked, ruler, culture)) => {
+                    let description = kingdom_description(ruler, culture, personality);
+                    kingdom_sidebar_generator(parent, &id, &name, &description, &unlocked)
+                }
+                None => {
+                    parent.spawn_bundle(column_perc(25., 100.));
+                }
+            }
         });
 }
 
@@ -360,15 +1276,33 @@ fn spawn_main_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
                         DisplayTypeEnum::StandardText("Start".to_string()),
                     ));
                 });
+            if save_exists(SAVE_PATH) {
+                menu.spawn_bundle(button(ButtonTypeEnum::SettingsButton))
+                    .insert(ButtonType(ButtonTypeEnum::SettingsButton))
+                    .insert(MainMenuContinueButton)
+                    .with_children(|button| {
+                        button.spawn_bundle(text(
+                            &asset_server,
+                            "Continue".to_string(),
+                            DisplayTypeEnum::StandardText("Continue".to_string()),
+                        ));
+                    });
+            }
         });
 }
 
 fn update_main_menu(
     mut state: ResMut<State<AppState>>,
     interaction_query: Query<&Interaction, (Changed<Interaction>, With<MainMenuStartButton>)>,
+    game_over: Res<GameOver>,
+    mut ev_new: EventWriter<NewGameEvent>,
 ) {
     for interaction in interaction_query.iter() {
         match *interaction {
+            // A finished game makes way for a new one, which `enter_loaded_game` then enters.
+            Interaction::Clicked if game_over.0.is_some() => {
+                ev_new.send(new_game());
+            }
             Interaction::Clicked => {
                 state.set(AppState::Playing).unwrap();
             }
@@ -378,6 +1312,148 @@ fn update_main_menu(
     }
 }
 
+fn continue_game(
+    interaction_query: Query<&Interaction, (Changed<Interaction>, With<MainMenuContinueButton>)>,
+    mut ev_load: EventWriter<LoadGameEvent>,
+) {
+    for interaction in interaction_query.iter() {
+        match *interaction {
+            Interaction::Clicked => match read_save(SAVE_PATH) {
+                Ok(snapshot) => ev_load.send(LoadGameEvent(snapshot)),
+                Err(error) => println!("Could not load the game: {}", error),
+            },
+            _ => {}
+        }
+    }
+}
+
+fn enter_loaded_game(
+    mut state: ResMut<State<AppState>>,
+    mut ev_loaded: EventReader<GameLoadedEvent>,
+) {
+    if ev_loaded.iter().next().is_some() {
+        state.set(AppState::Playing).unwrap();
+    }
+}
+
+// A new game with a fresh seed, printed so the run can be reproduced.
+fn new_game() -> NewGameEvent {
+    let seed = crate::random_seed();
+    println!("Seed: {}", seed);
+    return NewGameEvent { seed };
+}
+
+fn end_game(mut state: ResMut<State<AppState>>, mut ev_game_over: EventReader<GameOverEvent>) {
+    if ev_game_over.iter().next().is_some() {
+        state.set(AppState::GameOver).unwrap();
+    }
+}
+
+// Sums up the run: how it ended, how long it lasted and where each kingdom stands.
+fn spawn_game_over_screen(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    game_over: Res<GameOver>,
+    turn: Res<Turn>,
+    god: Res<GodStats>,
+    kingdom_query: Query<(&KingdomID, &Name), With<Kingdom>>,
+    resource_query: Query<(&Resource, &ResourceType, &KingdomID)>,
+) {
+    let (title, message) = match &game_over.0 {
+        Some(outcome) if outcome.victory => (
+            format!("Victory: {}", outcome.name),
+            outcome.message.clone(),
+        ),
+        Some(outcome) => (format!("Defeat: {}", outcome.name), outcome.message.clone()),
+        None => ("The End".to_string(), String::new()),
+    };
+    let mut kingdoms: Vec<(&KingdomID, &Name)> = kingdom_query.iter().collect();
+    kingdoms.sort_by_key(|(KingdomID(id), _)| *id);
+    let mut summary = vec![
+        message,
+        format!("Turns played: {}", turn.0),
+        format!("Faith remaining: {}", god.faith),
+    ];
+    for (kingdom, name) in kingdoms {
+        let resources = kingdom_snapshot(*kingdom, resource_query.iter());
+        let values = STARTING_RESOURCES
+            .iter()
+            .map(|resource| format!("{} {}", resource.as_ref(), resources.get(*resource)))
+            .collect::<Vec<String>>()
+            .join(", ");
+        summary.push(format!("{}: {}", name, values));
+    }
+
+    commands
+        .spawn_bundle(column_perc(100., 100.))
+        .insert(GameOverScreen)
+        .with_children(|screen| {
+            screen.spawn_bundle(text(
+                &asset_server,
+                title.clone(),
+                DisplayTypeEnum::StandardText(title),
+            ));
+            for line in summary {
+                screen.spawn_bundle(text(
+                    &asset_server,
+                    line.clone(),
+                    DisplayTypeEnum::SmallText(line),
+                ));
+            }
+            screen
+                .spawn_bundle(row_perc(100., -1.))
+                .with_children(|row| {
+                    row.spawn_bundle(button(ButtonTypeEnum::SettingsButton))
+                        .insert(GameOverRestartButton)
+                        .with_children(|button| {
+                            button.spawn_bundle(text(
+                                &asset_server,
+                                "Restart".to_string(),
+                                DisplayTypeEnum::StandardText("Restart".to_string()),
+                            ));
+                        });
+                    row.spawn_bundle(button(ButtonTypeEnum::SettingsButton))
+                        .insert(GameOverMenuButton)
+                        .with_children(|button| {
+                            button.spawn_bundle(text(
+                                &asset_server,
+                                "Main Menu".to_string(),
+                                DisplayTypeEnum::StandardText("Main Menu".to_string()),
+                            ));
+                        });
+                });
+        });
+}
+
+fn update_game_over_screen(
+    mut state: ResMut<State<AppState>>,
+    restart_query: Query<&Interaction, (Changed<Interaction>, With<GameOverRestartButton>)>,
+    menu_query: Query<&Interaction, (Changed<Interaction>, With<GameOverMenuButton>)>,
+    mut ev_new: EventWriter<NewGameEvent>,
+) {
+    for interaction in restart_query.iter() {
+        match *interaction {
+            Interaction::Clicked => ev_new.send(new_game()),
+            _ => {}
+        }
+    }
+    for interaction in menu_query.iter() {
+        match *interaction {
+            Interaction::Clicked => state.set(AppState::MainMenu).unwrap(),
+            _ => {}
+        }
+    }
+}
+
+fn remove_game_over_screen(
+    mut commands: Commands,
+    screen_query: Query<Entity, With<GameOverScreen>>,
+) {
+    for screen in screen_query.iter() {
+        commands.entity(screen).despawn_recursive();
+    }
+}
+
 fn remove_main_menu(mut commands: Commands, menu_query: Query<Entity, With<MainMenuScreen>>) {
     println!("Removing Main Menu");
     for menu in menu_query.iter() {
@@ -1,11 +1,13 @@
 use bevy::log::LogSettings;
 use bevy::prelude::*;
 
-use game::kingdom::*;
-use game::GamePlugin;
 use gui::GUIPlugin;
+use kingdom_click::game::kingdom::*;
+use kingdom_click::game::replay::{Replay, ReplayPlayer, ReplayRecorder, REPLAY_PATH};
+use kingdom_click::game::rng::GameRng;
+use kingdom_click::game::save::{Autosave, SAVE_PATH};
+use kingdom_click::game::GamePlugin;
 
-mod game;
 mod gui;
 
 const BACKGROUND_COLOR: Color = Color::rgb(0.05, 0.066, 0.09);
@@ -15,6 +17,7 @@ pub enum AppState {
     Loading,
     Playing,
     MainMenu,
+    GameOver,
 }
 
 // struct Handles {
@@ -37,9 +40,47 @@ fn main() {
         })
         .insert_resource(LogSettings {
             ..Default::default()
+        })
+        .insert_resource(Autosave(Some(SAVE_PATH.to_string())))
+        .insert_resource(ReplayRecorder {
+            path: Some(REPLAY_PATH.to_string()),
+            ..Default::default()
         });
 
-    app.add_state(AppState::MainMenu);
+    let args: Vec<String> = std::env::args().collect();
+
+    // `--seed <n>` makes a new game reproducible. Loading a save or replay restores its own seed.
+    let seed = match args.iter().position(|arg| arg == "--seed") {
+        Some(i) => args
+            .get(i + 1)
+            .and_then(|seed| seed.parse().ok())
+            .expect("--seed needs a number"),
+        None => random_seed(),
+    };
+    println!("Seed: {}", seed);
+    app.insert_resource(GameRng::new(seed));
+
+    // `--kingdoms <n>` sets how many kingdoms a new game starts with.
+    if let Some(i) = args.iter().position(|arg| arg == "--kingdoms") {
+        let kingdom_count = args
+            .get(i + 1)
+            .and_then(|count| count.parse().ok())
+            .expect("--kingdoms needs a number");
+        app.insert_resource(WorldSettings { kingdom_count });
+    }
+
+    // `--replay <file>` skips the menu and plays the recorded session back.
+    match args.iter().position(|arg| arg == "--replay") {
+        Some(i) => {
+            let path = args.get(i + 1).expect("--replay needs a file");
+            let replay = Replay::read(path).expect("Could not read the replay");
+            app.insert_resource(ReplayPlayer::new(replay));
+            app.add_state(AppState::Playing);
+        }
+        None => {
+            app.add_state(AppState::MainMenu);
+        }
+    }
 
     app.add_plugins(DefaultPlugins)
         .add_plugin(KingdomPlugin)
@@ -50,3 +91,16 @@ fn main() {
 
     app.run();
 }
+
+#[cfg(not(target_arch = "wasm32"))]
+fn random_seed() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|time| time.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn random_seed() -> u64 {
+    0
+}
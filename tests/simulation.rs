@@ -0,0 +1,260 @@
+use kingdom_click::game::effect::Effect;
+use kingdom_click::game::god::Upgrade;
+use kingdom_click::game::kingdom::KingdomID;
+use kingdom_click::game::resource::{ResourceBounds, ResourceTypes, STARTING_RESOURCES};
+use kingdom_click::game::save::GameSnapshot;
+use kingdom_click::game::simulation::Simulation;
+use kingdom_click::game::unrest::UNREST_TURNS;
+use kingdom_click::game::{ActionSource, ResourceAlterationEvent};
+
+fn values(sim: &mut Simulation, kingdom: KingdomID) -> Vec<isize> {
+    let resources = sim.resources(kingdom);
+    return STARTING_RESOURCES
+        .iter()
+        .map(|resource| resources.get(*resource))
+        .collect();
+}
+
+fn set(snapshot: &mut GameSnapshot, kingdom: KingdomID, resource: ResourceTypes, value: isize) {
+    let kingdom = snapshot
+        .kingdoms
+        .iter_mut()
+        .find(|saved| saved.id == kingdom)
+        .unwrap();
+    kingdom.resources.retain(|(saved, _)| *saved != resource);
+    kingdom.resources.push((resource, value));
+}
+
+// Plays until `kingdom`, already on the brink of rebellion, splits. Returns the loyalists and
+// the rebels.
+fn rebellion(sim: &mut Simulation, kingdom: KingdomID) -> (KingdomID, KingdomID) {
+    for _ in 0..30 {
+        // Keep the mobs angry until they rise.
+        decree(
+            sim,
+            vec![(kingdom, Effect::Set(ResourceTypes::Happiness, 0))],
+        );
+        let kingdoms = sim.kingdoms();
+        if kingdoms.iter().all(|(id, _)| *id != kingdom) {
+            let mut split: Vec<KingdomID> =
+                kingdoms.iter().rev().take(2).map(|(id, _)| *id).collect();
+            split.reverse();
+            return (split[0], split[1]);
+        }
+    }
+    panic!("{:?} never rebelled", kingdom);
+}
+
+// A populous, well-fed kingdom that has been restless long enough to rebel.
+fn restless(sim: &mut Simulation, kingdom: KingdomID) -> GameSnapshot {
+    let mut snapshot = sim.save();
+    set(&mut snapshot, kingdom, ResourceTypes::Populace, 100);
+    set(&mut snapshot, kingdom, ResourceTypes::Food, 100);
+    for saved in snapshot.kingdoms.iter_mut() {
+        if saved.id == kingdom {
+            saved.unrest = UNREST_TURNS;
+        }
+    }
+    return snapshot;
+}
+
+fn logged(sim: &Simulation, text: &str) -> bool {
+    sim.log()
+        .0
+        .iter()
+        .any(|(message, _)| message.contains(text))
+}
+
+// Plays one turn in which the god makes `changes`, for free.
+fn decree(sim: &mut Simulation, changes: Vec<(KingdomID, Effect)>) {
+    sim.act(ResourceAlterationEvent {
+        source: ActionSource::Player,
+        message: "Decree".to_string(),
+        changes,
+        cost: 0,
+    });
+}
+
+#[test]
+fn passing_plays_turns_headlessly() {
+    let mut sim = Simulation::with_seed(7);
+    assert!(!sim.kingdoms().is_empty());
+    for _ in 0..5 {
+        sim.pass();
+    }
+    assert_eq!(sim.turn(), 5);
+}
+
+#[test]
+fn same_seed_plays_the_same_game() {
+    let mut a = Simulation::with_seed(3);
+    let mut b = Simulation::with_seed(3);
+    for _ in 0..10 {
+        a.bless(KingdomID(1), ResourceTypes::Food);
+        b.bless(KingdomID(1), ResourceTypes::Food);
+    }
+    assert_eq!(a.kingdoms(), b.kingdoms());
+    for (kingdom, _) in a.kingdoms() {
+        assert_eq!(values(&mut a, kingdom), values(&mut b, kingdom));
+    }
+}
+
+#[test]
+fn unaffordable_actions_are_dropped() {
+    let mut sim = Simulation::with_seed(5);
+    let mut control = Simulation::with_seed(5);
+    let faith = sim.god().faith;
+    sim.act(ResourceAlterationEvent {
+        source: ActionSource::Player,
+        message: "Too dear".to_string(),
+        changes: vec![(KingdomID(1), Effect::Add(ResourceTypes::Food, 50))],
+        cost: faith + 1000,
+    });
+    control.pass();
+
+    // The turn plays out as if the god had passed.
+    assert_eq!(sim.god(), control.god());
+    assert_eq!(
+        values(&mut sim, KingdomID(1)),
+        values(&mut control, KingdomID(1))
+    );
+    assert!(logged(&sim, "falters for lack of faith"));
+}
+
+#[test]
+fn replays_play_back_the_same_game() {
+    let mut original = Simulation::with_seed(11);
+    // Start rich enough for every action to go through. Loading starts a fresh recording.
+    let mut start = original.save();
+    start.god.faith = 100;
+    original.load(start);
+    for turn in 0..20 {
+        match turn {
+            2 => original.buy(Upgrade::PassiveIncome),
+            5 => original.cast("Holy Crusade", Some(KingdomID(1)), None),
+            8 => original.cast("Divine Radiance", None, None),
+            _ if turn % 3 == 0 => original.bless(KingdomID(2), ResourceTypes::Faith),
+            _ => original.pass(),
+        }
+    }
+    assert_eq!(original.god().passive_income, 1);
+    let replay = original.replay().unwrap();
+    let mut replayed = Simulation::with_seed(11);
+    replayed.play_replay(&replay);
+
+    assert_eq!(replayed.turn(), original.turn());
+    assert_eq!(replayed.kingdoms(), original.kingdoms());
+    for (kingdom, _) in original.kingdoms() {
+        assert_eq!(
+            values(&mut replayed, kingdom),
+            values(&mut original, kingdom)
+        );
+    }
+    assert_eq!(replayed.god(), original.god());
+}
+
+#[test]
+fn loyalists_keep_the_kingdoms_relations() {
+    let mut sim = Simulation::with_seed(2);
+    let mut snapshot = restless(&mut sim, KingdomID(1));
+    for relation in snapshot.relations.iter_mut() {
+        relation.opinion = 70;
+        relation.treaty = true;
+        relation.trade = true;
+    }
+    sim.load(snapshot);
+    let (loyal, rebel) = rebellion(&mut sim, KingdomID(1));
+
+    let ally = sim.relations().get(loyal, KingdomID(2)).unwrap();
+    assert!(ally.treaty && ally.trade);
+    let civil_war = sim.relations().get(loyal, rebel).unwrap();
+    assert!(civil_war.war);
+    let stranger = sim.relations().get(rebel, KingdomID(2)).unwrap();
+    assert!(!stranger.treaty && !stranger.trade && !stranger.war);
+}
+
+#[test]
+fn rebellions_leave_both_sides_standing() {
+    let mut sim = Simulation::with_seed(4);
+    let snapshot = restless(&mut sim, KingdomID(1));
+    sim.load(snapshot);
+    let (loyal, rebel) = rebellion(&mut sim, KingdomID(1));
+    for _ in 0..10 {
+        sim.pass();
+    }
+    assert!(sim.outcome().is_none());
+    for kingdom in [loyal, rebel] {
+        assert!(sim.resources(kingdom).get(ResourceTypes::Populace) > 0);
+    }
+}
+
+#[test]
+fn kingdoms_too_small_to_split_do_not_rebel() {
+    let mut sim = Simulation::with_seed(4);
+    let mut snapshot = restless(&mut sim, KingdomID(1));
+    set(&mut snapshot, KingdomID(1), ResourceTypes::Populace, 40);
+    sim.load(snapshot);
+    for _ in 0..10 {
+        decree(
+            &mut sim,
+            vec![(KingdomID(1), Effect::Set(ResourceTypes::Happiness, 0))],
+        );
+    }
+    assert!(sim.kingdoms().iter().any(|(id, _)| *id == KingdomID(1)));
+}
+
+#[test]
+fn changes_stop_at_resource_bounds_and_say_so() {
+    let mut sim = Simulation::with_seed(6);
+    let kingdoms = sim.kingdoms();
+    let (first, first_name) = kingdoms[0].clone();
+    let (second, second_name) = kingdoms[1].clone();
+    decree(
+        &mut sim,
+        vec![
+            (first, Effect::Add(ResourceTypes::Happiness, 500)),
+            (second, Effect::Add(ResourceTypes::Food, -5000)),
+        ],
+    );
+
+    assert_eq!(sim.resources(first).get(ResourceTypes::Happiness), 100);
+    assert!(logged(
+        &sim,
+        &format!("{}'s Happiness cannot go above 100.", first_name)
+    ));
+    assert_eq!(sim.resources(second).get(ResourceTypes::Food), 0);
+    assert!(logged(
+        &sim,
+        &format!("{}'s Food cannot go below 0.", second_name)
+    ));
+}
+
+#[test]
+fn signed_changes_within_bounds_are_applied_whole() {
+    let mut sim = Simulation::with_seed(6);
+    sim.app
+        .world
+        .get_resource_mut::<ResourceBounds>()
+        .unwrap()
+        .set(ResourceTypes::Military, -50, 999);
+    let kingdom = sim.kingdoms()[0].0;
+    decree(
+        &mut sim,
+        vec![(kingdom, Effect::Set(ResourceTypes::Military, 10))],
+    );
+
+    decree(
+        &mut sim,
+        vec![(kingdom, Effect::Add(ResourceTypes::Military, -30))],
+    );
+    let military = sim.resources(kingdom).get(ResourceTypes::Military);
+    assert!(military < 0 && military > -50);
+    assert!(!logged(&sim, "Military cannot go"));
+
+    decree(
+        &mut sim,
+        vec![(kingdom, Effect::Add(ResourceTypes::Military, -500))],
+    );
+    assert_eq!(sim.resources(kingdom).get(ResourceTypes::Military), -50);
+    assert!(logged(&sim, "Military cannot go below -50."));
+}